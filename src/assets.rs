@@ -1,8 +1,13 @@
+use bevy::asset::LoadState;
 use bevy::prelude::*;
 
+use crate::skin::Skin;
+
 pub trait AssetServerExt {
     fn load_texture(&self, texture: &str) -> Handle<Image>;
     fn load_font(&self, texture: &str) -> Handle<Font>;
+    fn load_sound(&self, sound: &str) -> Handle<AudioSource>;
+    fn load_music(&self, music: &str) -> Handle<AudioSource>;
 }
 
 impl AssetServerExt for AssetServer {
@@ -13,6 +18,94 @@ impl AssetServerExt for AssetServer {
     fn load_font(&self, font: &str) -> Handle<Font> {
         self.load(&format!("fonts/{font}"))
     }
+
+    fn load_sound(&self, sound: &str) -> Handle<AudioSource> {
+        self.load(&format!("sounds/{sound}"))
+    }
+
+    fn load_music(&self, music: &str) -> Handle<AudioSource> {
+        self.load(&format!("music/{music}"))
+    }
+}
+
+/// Group of assets loaded at startup whose loading is tracked by the splash screen.
+pub trait AssetCollection {
+    /// Whether the game cannot run without these assets, as opposed to running without them.
+    const IS_REQUIRED: bool = true;
+
+    fn handles(&self) -> Vec<HandleUntyped>;
+}
+
+/// Handles waited for before leaving the splash screen.
+#[derive(Debug, Default)]
+pub struct LoadingAssets {
+    required: Vec<HandleUntyped>,
+    optional: Vec<HandleUntyped>,
+}
+
+/// Loading state of the [`LoadingAssets`], failed assets being reported by path.
+#[derive(Debug, Default)]
+pub struct LoadProgress {
+    pub settled: usize,
+    pub total: usize,
+    pub missing_required: Vec<String>,
+    pub missing_optional: Vec<String>,
+}
+
+impl LoadProgress {
+    pub fn ratio(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.settled as f32 / self.total as f32
+        }
+    }
+
+    /// Returns whether every asset either loaded or failed to.
+    pub fn is_settled(&self) -> bool {
+        self.settled == self.total
+    }
+}
+
+impl LoadingAssets {
+    pub fn add<T: AssetCollection>(&mut self, collection: &T) -> &mut Self {
+        if T::IS_REQUIRED {
+            self.required.extend(collection.handles());
+        } else {
+            self.optional.extend(collection.handles());
+        }
+
+        self
+    }
+
+    pub fn progress(&self, asset_server: &AssetServer) -> LoadProgress {
+        let mut progress = LoadProgress {
+            total: self.required.len() + self.optional.len(),
+            ..Default::default()
+        };
+
+        for (handles, missing) in [
+            (&self.required, &mut progress.missing_required),
+            (&self.optional, &mut progress.missing_optional),
+        ] {
+            for handle in handles {
+                match asset_server.get_load_state(handle) {
+                    LoadState::Loaded => progress.settled += 1,
+                    LoadState::Failed => {
+                        progress.settled += 1;
+                        missing.push(
+                            asset_server
+                                .get_handle_path(handle)
+                                .map_or_else(|| format!("{:?}", handle.id), |path| path.path().display().to_string()),
+                        );
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        progress
+    }
 }
 
 pub struct FontAssets {
@@ -29,6 +122,13 @@ impl FromWorld for FontAssets {
     }
 }
 
+impl AssetCollection for FontAssets {
+    fn handles(&self) -> Vec<HandleUntyped> {
+        vec![self.text.clone_untyped()]
+    }
+}
+
+#[derive(Debug)]
 pub struct SnakeFragmentTextureAssets {
     pub straight: Handle<Image>,
     pub right_curved: Handle<Image>,
@@ -38,9 +138,6 @@ pub struct SnakeFragmentTextureAssets {
 pub struct TextureAssets {
     pub apple: Handle<Image>,
     pub apple_leaf: Handle<Image>,
-    pub snake_head: Handle<Image>,
-    pub snake_tail: Handle<Image>,
-    pub snake_fragment_assets: Vec<SnakeFragmentTextureAssets>,
     pub bush_lower: Handle<Image>,
     pub bush_upper: Handle<Image>,
 }
@@ -49,23 +146,140 @@ impl FromWorld for TextureAssets {
     fn from_world(world: &mut World) -> Self {
         let asset_server = world.get_resource::<AssetServer>().unwrap();
 
-        let snake_fragment_assets = (1..=7)
-            .into_iter()
-            .map(|i| SnakeFragmentTextureAssets {
-                straight: asset_server.load_texture(&format!("snake_fragment_{i}.png")),
-                right_curved: asset_server.load_texture(&format!("snake_fragment_right_{i}.png")),
-                left_curved: asset_server.load_texture(&format!("snake_fragment_left_{i}.png")),
-            })
-            .collect();
-
         Self {
-            snake_fragment_assets,
             apple: asset_server.load_texture("apple.png"),
             apple_leaf: asset_server.load_texture("apple_leaf.png"),
-            snake_head: asset_server.load_texture("snake_head.png"),
-            snake_tail: asset_server.load_texture("snake_tail.png"),
             bush_lower: asset_server.load_texture("bush_lower.png"),
             bush_upper: asset_server.load_texture("bush_upper.png"),
         }
     }
 }
+
+/// Indices of a variant of snake fragment in the [`SnakeAtlas`].
+#[derive(Debug, Copy, Clone)]
+pub struct SnakeFragmentAtlasIndices {
+    pub straight: usize,
+    pub right_curved: usize,
+    pub left_curved: usize,
+}
+
+/// Snake textures packed together so that a whole snake is drawn from a single texture.
+pub struct SnakeAtlas {
+    /// Identifier of the skin the textures come from.
+    pub skin: String,
+    pub atlas: Handle<TextureAtlas>,
+    pub head: usize,
+    pub tail: usize,
+    pub fragments: Vec<SnakeFragmentAtlasIndices>,
+    /// Tongue of the skin, drawn apart from the atlas since it sticks out of the head.
+    pub tongue: Option<Handle<Image>>,
+}
+
+impl SnakeAtlas {
+    /// Packs the textures of the skin, returning `None` until all of them are loaded.
+    pub fn build(skin: &Skin, images: &mut Assets<Image>, atlases: &mut Assets<TextureAtlas>) -> Option<Self> {
+        let handles = std::iter::once(&skin.head)
+            .chain(std::iter::once(&skin.tail))
+            .chain(
+                skin.fragments
+                    .iter()
+                    .flat_map(|assets| [&assets.straight, &assets.right_curved, &assets.left_curved]),
+            )
+            .collect::<Vec<_>>();
+
+        let mut builder = TextureAtlasBuilder::default();
+
+        for handle in handles {
+            builder.add_texture(handle.clone(), images.get(handle)?);
+        }
+
+        let atlas = match builder.finish(images) {
+            Ok(atlas) => atlas,
+            Err(e) => {
+                error!("cannot pack the snake textures: {e:?}");
+
+                return None;
+            }
+        };
+
+        let index = |handle: &Handle<Image>| atlas.get_texture_index(handle).unwrap_or_default();
+
+        let head = index(&skin.head);
+        let tail = index(&skin.tail);
+        let fragments = skin
+            .fragments
+            .iter()
+            .map(|assets| SnakeFragmentAtlasIndices {
+                straight: index(&assets.straight),
+                right_curved: index(&assets.right_curved),
+                left_curved: index(&assets.left_curved),
+            })
+            .collect();
+
+        Some(Self {
+            skin: skin.id.clone(),
+            atlas: atlases.add(atlas),
+            head,
+            tail,
+            fragments,
+            tongue: skin.tongue.clone(),
+        })
+    }
+}
+
+impl AssetCollection for TextureAssets {
+    fn handles(&self) -> Vec<HandleUntyped> {
+        vec![
+            self.apple.clone_untyped(),
+            self.apple_leaf.clone_untyped(),
+            self.bush_lower.clone_untyped(),
+            self.bush_upper.clone_untyped(),
+        ]
+    }
+}
+
+pub struct AudioAssets {
+    pub eat: Handle<AudioSource>,
+    pub turn: Handle<AudioSource>,
+    pub apple_expire: Handle<AudioSource>,
+    pub wind: Handle<AudioSource>,
+    pub collision: Handle<AudioSource>,
+    pub menu_move: Handle<AudioSource>,
+    pub menu_select: Handle<AudioSource>,
+    pub music: Handle<AudioSource>,
+}
+
+impl FromWorld for AudioAssets {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.get_resource::<AssetServer>().unwrap();
+
+        Self {
+            eat: asset_server.load_sound("eat.ogg"),
+            turn: asset_server.load_sound("turn.ogg"),
+            apple_expire: asset_server.load_sound("apple_expire.ogg"),
+            wind: asset_server.load_sound("wind.ogg"),
+            collision: asset_server.load_sound("collision.ogg"),
+            menu_move: asset_server.load_sound("menu_move.ogg"),
+            menu_select: asset_server.load_sound("menu_select.ogg"),
+            music: asset_server.load_music("meadow.ogg"),
+        }
+    }
+}
+
+/// The game stays playable without sound, so missing sounds are only reported.
+impl AssetCollection for AudioAssets {
+    const IS_REQUIRED: bool = false;
+
+    fn handles(&self) -> Vec<HandleUntyped> {
+        vec![
+            self.eat.clone_untyped(),
+            self.turn.clone_untyped(),
+            self.apple_expire.clone_untyped(),
+            self.wind.clone_untyped(),
+            self.collision.clone_untyped(),
+            self.menu_move.clone_untyped(),
+            self.menu_select.clone_untyped(),
+            self.music.clone_untyped(),
+        ]
+    }
+}
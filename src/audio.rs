@@ -0,0 +1,125 @@
+use bevy::audio::AudioSink;
+use bevy::prelude::*;
+
+use crate::assets::AudioAssets;
+use crate::game::snake::MoveTimer;
+use crate::screens::Persistent;
+use crate::settings::{Settings, SnakeSpeed, VolumeCategory};
+
+/// Requests a one-shot sound, played at the volume of the effects.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SoundEffect {
+    Eat,
+    Turn,
+    AppleExpire,
+    Wind,
+    Collision,
+    MenuMove,
+    MenuSelect,
+}
+
+impl SoundEffect {
+    fn source(self, sounds: &AudioAssets) -> Handle<AudioSource> {
+        match self {
+            Self::Eat => sounds.eat.clone(),
+            Self::Turn => sounds.turn.clone(),
+            Self::AppleExpire => sounds.apple_expire.clone(),
+            Self::Wind => sounds.wind.clone(),
+            Self::Collision => sounds.collision.clone(),
+            Self::MenuMove => sounds.menu_move.clone(),
+            Self::MenuSelect => sounds.menu_select.clone(),
+        }
+    }
+}
+
+/// Background music, looping for as long as the game runs.
+#[derive(Component)]
+struct Music {
+    sink: Handle<AudioSink>,
+    speed: f32,
+}
+
+pub struct AudioPlugin;
+
+impl Plugin for AudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SoundEffect>()
+            .add_startup_system(play_music)
+            .add_system(play_sound_effects)
+            .add_system(update_music_volume)
+            .add_system(update_music_tempo);
+    }
+}
+
+fn play_music(
+    mut commands: Commands,
+    audio: Res<Audio>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    sounds: Res<AudioAssets>,
+    settings: Res<Settings>,
+) {
+    let sink = audio.play_with_settings(
+        sounds.music.clone(),
+        PlaybackSettings::LOOP.with_volume(settings.audio.volume(VolumeCategory::Music)),
+    );
+
+    commands
+        .spawn()
+        .insert(Music {
+            sink: audio_sinks.get_handle(sink),
+            speed: 1.0,
+        })
+        .insert(Persistent);
+}
+
+fn play_sound_effects(
+    mut sound_reader: EventReader<SoundEffect>,
+    audio: Res<Audio>,
+    sounds: Res<AudioAssets>,
+    settings: Res<Settings>,
+) {
+    let volume = settings.audio.volume(VolumeCategory::Effects);
+
+    if volume <= 0.0 {
+        sound_reader.clear();
+
+        return;
+    }
+
+    for sound in sound_reader.iter() {
+        audio.play_with_settings(sound.source(&sounds), PlaybackSettings::ONCE.with_volume(volume));
+    }
+}
+
+fn update_music_volume(settings: Res<Settings>, music_query: Query<&Music>, audio_sinks: Res<Assets<AudioSink>>) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    music_query
+        .iter()
+        .filter_map(|music| audio_sinks.get(&music.sink))
+        .for_each(|sink| sink.set_volume(settings.audio.volume(VolumeCategory::Music)));
+}
+
+/// Speeds the music up along with the snakes, relative to their normal speed, and slows it back down in the menus.
+fn update_music_tempo(
+    mut music_query: Query<&mut Music>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    timer_query: Query<&MoveTimer>,
+) {
+    let Ok(mut music) = music_query.get_single_mut() else {
+        return;
+    };
+
+    let speed = timer_query.iter().next().map_or(1.0, |timer| {
+        SnakeSpeed::Normal.move_interval() / timer.duration().as_secs_f32().max(f32::EPSILON)
+    });
+
+    if (speed - music.speed).abs() > f32::EPSILON {
+        if let Some(sink) = audio_sinks.get(&music.sink) {
+            sink.set_speed(speed);
+            music.speed = speed;
+        }
+    }
+}
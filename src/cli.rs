@@ -0,0 +1,262 @@
+use bevy::prelude::*;
+use derive_more::Display;
+use iyes_loopless::prelude::*;
+
+use crate::game::level::CurrentLevel;
+use crate::game::play_mode::PlayMode;
+use crate::settings::{DisplayMode, Override, Settings, SettingsOverrides, SnakeSpeed};
+use crate::states::{AppScreen, GameMode};
+
+/// Options taking a value, every option but the help one.
+const OPTIONS: [&str; 7] = [
+    "--screen",
+    "--map",
+    "--seed",
+    "--players",
+    "--play-mode",
+    "--speed",
+    "--window",
+];
+
+pub const USAGE: &str = "\
+Usage: bevy-snake2d [OPTIONS]
+
+Options:
+  --screen <SCREEN>  Screen to start on: splash, main-menu, in-game, editor, settings, controls
+                     or level-select
+  --map <FILE>       Map to play, relative to the assets folder
+  --seed <SEED>      Seed of the random number generators, to get the same apples again
+  --players <COUNT>  Number of players: single or multi
+  --play-mode <MODE> Rules of the game: endless, time-attack-60, time-attack-120, survival or zen,
+                     regardless of the settings
+  --speed <SPEED>    Snake speed: slow, normal or fast, regardless of the settings
+  --window <MODE>    Window mode: windowed or fullscreen, regardless of the settings
+  -h, --help         Print this help";
+
+#[derive(Debug, Display, PartialEq, Eq)]
+pub enum CliError {
+    #[display(fmt = "missing value for {_0}")]
+    MissingValue(String),
+    #[display(fmt = "invalid value for {_0}: {_1}")]
+    InvalidValue(String, String),
+    #[display(fmt = "unknown option {_0}")]
+    UnknownOption(String),
+    #[display(fmt = "help requested")]
+    Help,
+}
+
+impl std::error::Error for CliError {}
+
+/// Options given on the command line to jump straight into a specific scenario.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LaunchOptions {
+    pub screen: Option<AppScreen>,
+    pub map: Option<String>,
+    pub seed: Option<u64>,
+    pub players: Option<GameMode>,
+    pub play_mode: Option<PlayMode>,
+    pub speed: Option<SnakeSpeed>,
+    pub window_mode: Option<DisplayMode>,
+}
+
+impl LaunchOptions {
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, CliError> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            // accept both `--flag value` and `--flag=value`
+            let (flag, value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+
+            if flag == "-h" || flag == "--help" {
+                return Err(CliError::Help);
+            }
+
+            if !OPTIONS.contains(&flag.as_str()) {
+                return Err(CliError::UnknownOption(flag));
+            }
+
+            let value = value
+                .or_else(|| args.next())
+                .ok_or_else(|| CliError::MissingValue(flag.clone()))?;
+            let invalid = || CliError::InvalidValue(flag.clone(), value.clone());
+
+            match flag.as_str() {
+                "--screen" => options.screen = Some(parse_screen(&value).ok_or_else(invalid)?),
+                "--map" => options.map = Some(value.clone()),
+                "--seed" => options.seed = Some(value.parse().map_err(|_| invalid())?),
+                "--players" => options.players = Some(parse_players(&value).ok_or_else(invalid)?),
+                "--play-mode" => options.play_mode = Some(parse_play_mode(&value).ok_or_else(invalid)?),
+                "--speed" => options.speed = Some(parse_speed(&value).ok_or_else(invalid)?),
+                "--window" => options.window_mode = Some(parse_window_mode(&value).ok_or_else(invalid)?),
+                _ => unreachable!("{flag} is not a known option"),
+            }
+        }
+
+        Ok(options)
+    }
+
+    /// Overrides the settings in memory, returning the overrides so that the persisted values are written back in
+    /// their place.
+    pub fn apply_to(&self, settings: &mut Settings) -> SettingsOverrides {
+        let overrides = SettingsOverrides {
+            snake_speed: self.speed.map(|value| Override {
+                value,
+                persisted: settings.snake_speed,
+            }),
+            window_mode: self.window_mode.map(|value| Override {
+                value,
+                persisted: settings.window.mode,
+            }),
+            play_mode: self.play_mode.map(|value| Override {
+                value,
+                persisted: settings.play_mode,
+            }),
+        };
+
+        if let Some(speed) = self.speed {
+            settings.snake_speed = speed;
+        }
+
+        if let Some(window_mode) = self.window_mode {
+            settings.window.mode = window_mode;
+        }
+
+        if let Some(play_mode) = self.play_mode {
+            settings.play_mode = play_mode;
+        }
+
+        overrides
+    }
+}
+
+fn parse_screen(value: &str) -> Option<AppScreen> {
+    match value {
+        "splash" => Some(AppScreen::Splash),
+        "main-menu" => Some(AppScreen::MainMenu),
+        "in-game" => Some(AppScreen::InGame),
+        "editor" => Some(AppScreen::Editor),
+        "settings" => Some(AppScreen::Settings),
+        "controls" => Some(AppScreen::Controls),
+        "level-select" => Some(AppScreen::LevelSelect),
+        _ => None,
+    }
+}
+
+fn parse_players(value: &str) -> Option<GameMode> {
+    match value {
+        "single" => Some(GameMode::SinglePlayer),
+        "multi" => Some(GameMode::MultiPlayer),
+        _ => None,
+    }
+}
+
+fn parse_play_mode(value: &str) -> Option<PlayMode> {
+    match value {
+        "endless" => Some(PlayMode::Endless),
+        "time-attack-60" => Some(PlayMode::TimeAttack60),
+        "time-attack-120" => Some(PlayMode::TimeAttack120),
+        "survival" => Some(PlayMode::Survival),
+        "zen" => Some(PlayMode::Zen),
+        _ => None,
+    }
+}
+
+fn parse_speed(value: &str) -> Option<SnakeSpeed> {
+    match value {
+        "slow" => Some(SnakeSpeed::Slow),
+        "normal" => Some(SnakeSpeed::Normal),
+        "fast" => Some(SnakeSpeed::Fast),
+        _ => None,
+    }
+}
+
+fn parse_window_mode(value: &str) -> Option<DisplayMode> {
+    match value {
+        "windowed" => Some(DisplayMode::Windowed),
+        "fullscreen" => Some(DisplayMode::Fullscreen),
+        _ => None,
+    }
+}
+
+pub struct CliPlugin;
+
+impl Plugin for CliPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LaunchOptions>()
+            .add_startup_system(apply_launch_options);
+    }
+}
+
+/// Applies the options which need the screens to be set up, the settings being overridden before creating the window.
+fn apply_launch_options(mut commands: Commands, options: Res<LaunchOptions>, asset_server: Res<AssetServer>) {
+    if let Some(map) = &options.map {
+        commands.insert_resource(CurrentLevel(asset_server.load(map.as_str())));
+    }
+
+    if let Some(players) = options.players {
+        commands.insert_resource(players);
+    }
+
+    if let Some(screen) = options.screen {
+        commands.insert_resource(NextState(screen));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CliError, LaunchOptions};
+    use crate::game::play_mode::PlayMode;
+    use crate::settings::{DisplayMode, Settings, SnakeSpeed};
+    use crate::states::{AppScreen, GameMode};
+
+    fn parse(args: &[&str]) -> Result<LaunchOptions, CliError> {
+        LaunchOptions::parse(args.iter().map(ToString::to_string))
+    }
+
+    #[test]
+    fn it_parses_options() {
+        let options = parse(&["--screen", "editor", "--seed=42", "--speed", "fast", "--window", "fullscreen"]).unwrap();
+
+        assert_eq!(options.screen, Some(AppScreen::Editor));
+        assert_eq!(options.seed, Some(42));
+        assert_eq!(options.speed, Some(SnakeSpeed::Fast));
+        assert_eq!(options.window_mode, Some(DisplayMode::Fullscreen));
+        assert_eq!(options.map, None);
+
+        let options = parse(&["--players", "multi", "--play-mode", "time-attack-60"]).unwrap();
+
+        assert_eq!(options.players, Some(GameMode::MultiPlayer));
+        assert_eq!(options.play_mode, Some(PlayMode::TimeAttack60));
+    }
+
+    #[test]
+    fn it_rejects_invalid_options() {
+        assert_eq!(
+            parse(&["--players", "coop"]),
+            Err(CliError::InvalidValue("--players".to_string(), "coop".to_string()))
+        );
+        assert_eq!(parse(&["--seed"]), Err(CliError::MissingValue("--seed".to_string())));
+        assert_eq!(parse(&["--fps", "60"]), Err(CliError::UnknownOption("--fps".to_string())));
+        assert_eq!(parse(&["--help"]), Err(CliError::Help));
+    }
+
+    #[test]
+    fn it_keeps_overrides_out_of_persisted_settings() {
+        let mut settings = Settings::default();
+        let overrides = parse(&["--speed", "fast", "--play-mode", "zen"])
+            .unwrap()
+            .apply_to(&mut settings);
+
+        assert_eq!(settings.snake_speed, SnakeSpeed::Fast);
+        assert_eq!(overrides.persisted(&settings), Settings::default());
+
+        // a setting changed while playing is no longer an override
+        settings.play_mode = PlayMode::Survival;
+        assert_eq!(overrides.persisted(&settings).play_mode, PlayMode::Survival);
+        assert_eq!(overrides.persisted(&settings).snake_speed, SnakeSpeed::default());
+    }
+}
@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+use bevy::window::WindowResized;
+
+use crate::settings::{DisplayMode, Settings};
+
+/// Window height for which the user interface was designed.
+const REFERENCE_HEIGHT: f32 = 1080.;
+
+/// Ratio between the window height and [`REFERENCE_HEIGHT`], applied to every scaled UI element.
+#[derive(Debug, Deref)]
+pub struct UiScale(f32);
+
+impl Default for UiScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// Font size of a text at the reference resolution, applied to all of its sections.
+#[derive(Debug, Component)]
+pub struct ScaledFont(pub f32);
+
+/// Size of a UI node at the reference resolution.
+#[derive(Debug, Component)]
+pub struct ScaledSize(pub Vec2);
+
+pub struct DisplayPlugin;
+
+impl Plugin for DisplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UiScale>()
+            .add_system(toggle_fullscreen)
+            .add_system(apply_window_mode.after(toggle_fullscreen))
+            .add_system(remember_window_size)
+            .add_system(update_ui_scale)
+            .add_system(scale_fonts.after(update_ui_scale))
+            .add_system(scale_sizes.after(update_ui_scale));
+    }
+}
+
+fn toggle_fullscreen(keys: Res<Input<KeyCode>>, mut settings: ResMut<Settings>) {
+    let is_alt_pressed = keys.any_pressed([KeyCode::LAlt, KeyCode::RAlt]);
+
+    if keys.just_pressed(KeyCode::F11) || (is_alt_pressed && keys.just_pressed(KeyCode::Return)) {
+        settings.window.mode = match settings.window.mode {
+            DisplayMode::Windowed => DisplayMode::Fullscreen,
+            DisplayMode::Fullscreen => DisplayMode::Windowed,
+        };
+    }
+}
+
+/// Applies the window mode of the settings, whether it comes from a shortcut or from the settings screen.
+fn apply_window_mode(settings: Res<Settings>, mut windows: ResMut<Windows>) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    if let Some(window) = windows.get_primary_mut() {
+        let mode = settings.window.mode.into();
+
+        if window.mode() != mode {
+            window.set_mode(mode);
+        }
+    }
+}
+
+fn remember_window_size(mut resize_reader: EventReader<WindowResized>, mut settings: ResMut<Settings>) {
+    let event = resize_reader.iter().rfind(|event| event.id.is_primary());
+
+    // only remember the size the user chose, not the one of the screen
+    if let (Some(event), DisplayMode::Windowed) = (event, settings.window.mode) {
+        settings.window.width = event.width;
+        settings.window.height = event.height;
+    }
+}
+
+fn update_ui_scale(windows: Res<Windows>, mut ui_scale: ResMut<UiScale>) {
+    if let Some(window) = windows.get_primary() {
+        let scale = window.height() / REFERENCE_HEIGHT;
+
+        if (ui_scale.0 - scale).abs() > f32::EPSILON {
+            ui_scale.0 = scale;
+        }
+    }
+}
+
+fn scale_fonts(ui_scale: Res<UiScale>, mut query: Query<(&mut Text, &ScaledFont, ChangeTrackers<ScaledFont>)>) {
+    query.for_each_mut(|(mut text, font, font_tracker)| {
+        if ui_scale.is_changed() || font_tracker.is_changed() {
+            text.sections
+                .iter_mut()
+                .for_each(|section| section.style.font_size = font.0 * ui_scale.0);
+        }
+    });
+}
+
+fn scale_sizes(ui_scale: Res<UiScale>, mut query: Query<(&mut Style, &ScaledSize, ChangeTrackers<ScaledSize>)>) {
+    query.for_each_mut(|(mut style, size, size_tracker)| {
+        if ui_scale.is_changed() || size_tracker.is_changed() {
+            style.size = Size::new(Val::Px(size.0.x * ui_scale.0), Val::Px(size.0.y * ui_scale.0));
+        }
+    });
+}
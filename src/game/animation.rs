@@ -0,0 +1,250 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_tweening::lens::{TransformPositionLens, TransformScaleLens};
+use bevy_tweening::{Animator, EaseFunction, Tracks, Tween, TweeningType};
+use iyes_loopless::prelude::*;
+
+use super::components::Detached;
+use super::snake::{Dead, MoveTimer, Snake, SnakeEvent};
+use super::tile_map::TileMapOptions;
+use super::{GameStage, GameSystem};
+
+/// Time during which the fragments of a dead snake flash, in seconds.
+const FLASH_DURATION: f32 = 0.6;
+const FLASH_INTERVAL: f32 = 0.1;
+const FLASH_COLOR: Color = Color::rgb(1.0, 0.3, 0.3);
+/// Time taken by the fragments of a dead snake to scatter, in seconds.
+const SCATTER_DURATION: f32 = 0.8;
+/// Time left to look at, or rewind, the remains of the snake before the game is over, in seconds.
+const GAME_OVER_DELAY: f32 = 0.4;
+const BULGE_SCALE: Vec3 = Vec3::new(1.0, 1.35, 1.0);
+/// Angle between the scatter directions of two successive fragments, so that neighbours fly apart.
+const GOLDEN_ANGLE: f32 = 2.399_963;
+
+/// Indices of the fragments bulging with an apple, travelling from the head to the tail as the snake moves.
+#[derive(Debug, Default, Component)]
+pub struct Swallow(Vec<usize>);
+
+/// Flashes the fragments of a dead snake then scatters them, before the game is over.
+#[derive(Debug, Component)]
+pub struct DeathSequence {
+    timer: Timer,
+    is_scattered: bool,
+}
+
+impl Default for DeathSequence {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(FLASH_DURATION + SCATTER_DURATION + GAME_OVER_DELAY, false),
+            is_scattered: false,
+        }
+    }
+}
+
+/// Every snake is dead, and done dying.
+#[derive(Debug)]
+pub struct GameOver;
+
+pub struct SnakeAnimationPlugin;
+
+impl Plugin for SnakeAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GameOver>()
+            .add_system(
+                move_swallows
+                    .after(GameSystem::Movement)
+                    .before(GameSystem::CollisionDetection),
+            )
+            .add_system(animate_growth.after(GameSystem::CollisionDetection))
+            .add_system_set_to_stage(
+                GameStage::Transform,
+                SystemSet::new()
+                    .with_system(start_death_sequence)
+                    .with_system(play_death_sequence.run_if_resource_exists::<TileMapOptions>())
+                    .with_system(stop_death_sequence),
+            )
+            .add_system_to_stage(GameStage::Cleanup, check_game_over);
+    }
+}
+
+/// Moves the bulges one fragment further every time the snake moves, until they go past its tail.
+fn move_swallows(mut commands: Commands, mut snake_query: Query<(&Snake, &mut Swallow, &MoveTimer), Without<Dead>>) {
+    snake_query.for_each_mut(|(snake, mut swallow, timer)| {
+        if !timer.just_finished() || swallow.0.is_empty() {
+            return;
+        }
+
+        swallow.0.iter_mut().for_each(|index| *index += 1);
+        swallow.0.retain(|index| *index < snake.fragments.len());
+
+        for index in &swallow.0 {
+            bulge(&mut commands, snake.fragments[*index], timer.duration());
+        }
+    });
+}
+
+/// Pops the new tail in, and starts a bulge at the head of the snakes which just ate.
+fn animate_growth(
+    mut commands: Commands,
+    mut snake_reader: EventReader<SnakeEvent>,
+    mut snake_query: Query<(&Snake, &mut Swallow, &MoveTimer)>,
+) {
+    for event in snake_reader.iter() {
+        let (snake, tail) = match event {
+            SnakeEvent::Grow { snake, tail } => (snake, tail),
+            SnakeEvent::HeadCollide { .. } => continue,
+        };
+
+        commands.entity(*tail).insert(Animator::new(Tween::new(
+            EaseFunction::BackOut,
+            TweeningType::Once,
+            Duration::from_millis(250),
+            TransformScaleLens {
+                start: Vec3::ZERO,
+                end: Vec3::ONE,
+            },
+        )));
+
+        if let Ok((snake, mut swallow, timer)) = snake_query.get_mut(*snake) {
+            swallow.0.push(0);
+            bulge(&mut commands, snake.fragments[0], timer.duration());
+        }
+    }
+}
+
+/// Swells the fragment across the snake, then lets it shrink back by the time the bulge moves on.
+fn bulge(commands: &mut Commands, fragment: Entity, duration: Duration) {
+    commands.entity(fragment).insert(Animator::new(Tween::new(
+        EaseFunction::QuadraticOut,
+        TweeningType::Once,
+        duration,
+        TransformScaleLens {
+            start: BULGE_SCALE,
+            end: Vec3::ONE,
+        },
+    )));
+}
+
+fn start_death_sequence(mut commands: Commands, query: Query<Entity, (With<Snake>, Added<Dead>)>) {
+    query.for_each(|entity| {
+        commands.entity(entity).insert(DeathSequence::default());
+    });
+}
+
+fn play_death_sequence(
+    mut commands: Commands,
+    mut snake_query: Query<(&Snake, &mut DeathSequence)>,
+    mut fragment_query: Query<(&Transform, &mut TextureAtlasSprite)>,
+    tile_map_options: Res<TileMapOptions>,
+    time: Res<Time>,
+) {
+    snake_query.for_each_mut(|(snake, mut sequence)| {
+        let elapsed = sequence.timer.tick(time.delta()).elapsed_secs();
+
+        if elapsed < FLASH_DURATION {
+            let color = if (elapsed / FLASH_INTERVAL).rem_euclid(2.0) < 1.0 {
+                FLASH_COLOR
+            } else {
+                Color::WHITE
+            };
+
+            // the head keeps its dizzy look
+            let mut iter = fragment_query.iter_many_mut(&snake.fragments[1..]);
+
+            while let Some((_, mut sprite)) = iter.fetch_next() {
+                sprite.color = color;
+            }
+
+            return;
+        }
+
+        if sequence.is_scattered {
+            return;
+        }
+
+        sequence.is_scattered = true;
+
+        for (i, fragment) in snake.fragments.iter().enumerate() {
+            let Ok((transform, mut sprite)) = fragment_query.get_mut(*fragment) else {
+                continue;
+            };
+
+            if i > 0 {
+                sprite.color = Color::WHITE;
+            }
+
+            let angle = i as f32 * GOLDEN_ANGLE;
+            let offset = Vec2::new(angle.cos(), angle.sin()) * tile_map_options.tile_size * 1.5;
+
+            commands.entity(*fragment).insert_bundle((
+                Detached,
+                Animator::new(Tracks::new([
+                    Tween::new(
+                        EaseFunction::QuadraticOut,
+                        TweeningType::Once,
+                        Duration::from_secs_f32(SCATTER_DURATION),
+                        TransformPositionLens {
+                            start: transform.translation,
+                            end: transform.translation + offset.extend(0.0),
+                        },
+                    ),
+                    Tween::new(
+                        EaseFunction::QuadraticIn,
+                        TweeningType::Once,
+                        Duration::from_secs_f32(SCATTER_DURATION),
+                        TransformScaleLens {
+                            start: Vec3::ONE,
+                            end: Vec3::ZERO,
+                        },
+                    ),
+                ])),
+            ));
+        }
+    });
+}
+
+/// Puts the fragments of the snakes back together once they are alive again, e.g. after a rewind.
+fn stop_death_sequence(
+    mut commands: Commands,
+    revived: RemovedComponents<Dead>,
+    snake_query: Query<&Snake>,
+    mut fragment_query: Query<(&mut Transform, &mut TextureAtlasSprite)>,
+) {
+    for entity in revived.iter() {
+        let Ok(snake) = snake_query.get(entity) else {
+            continue;
+        };
+
+        commands.entity(entity).remove::<DeathSequence>();
+
+        for (i, fragment) in snake.fragments.iter().enumerate() {
+            commands
+                .entity(*fragment)
+                .remove::<Detached>()
+                .remove::<Animator<Transform>>();
+
+            if let Ok((mut transform, mut sprite)) = fragment_query.get_mut(*fragment) {
+                transform.scale = Vec3::ONE;
+
+                // the head gets its own color back along with its face
+                if i > 0 {
+                    sprite.color = Color::WHITE;
+                }
+            }
+        }
+    }
+}
+
+fn check_game_over(mut game_over_writer: EventWriter<GameOver>, query: Query<Option<&DeathSequence>, With<Snake>>) {
+    let is_just_over = query
+        .iter()
+        .any(|sequence| sequence.is_some_and(|sequence| sequence.timer.just_finished()));
+    let is_over = query
+        .iter()
+        .all(|sequence| sequence.is_some_and(|sequence| sequence.timer.finished()));
+
+    if is_just_over && is_over {
+        game_over_writer.send(GameOver);
+    }
+}
@@ -1,27 +1,45 @@
 use std::f32::consts::PI;
+use std::marker::PhantomData;
 use std::time::Duration;
 
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy_tweening::lens::{TransformRotateZLens, TransformRotationLens, TransformScaleLens};
 use bevy_tweening::{Animator, EaseFunction, Tween, TweeningType};
 use iyes_loopless::prelude::*;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use super::components::{Lifetime, Position, RigidBody};
-use super::tile_map::TileMap;
+use super::particles::{ParticleBurst, ParticleConfig};
+use super::rng::GameRng;
+use super::tile_map::{Tile, TileMap};
 use super::GameSystem;
 use crate::assets::TextureAssets;
+use crate::audio::SoundEffect;
 use crate::systems;
 
 #[derive(Debug, Default, Component, Reflect)]
 #[reflect(Component)]
 pub struct Apple;
 
+const GOLDEN_APPLE_COLOR: Color = Color::rgb(1.0, 0.8, 0.2);
+
+/// Rare apple granting a [`BushCutter`](super::bush::BushCutter) charge to the snake eating it.
+#[derive(Debug, Default, Component)]
+pub struct GoldenApple;
+
+/// An apple rotted away before any snake could eat it.
+#[derive(Debug)]
+pub struct AppleExpired;
+
 #[derive(Default)]
 pub struct AppleBuilder {
     position: Option<IVec3>,
     angle: Option<f32>,
     is_animated: bool,
+    is_golden: bool,
+    lifetime: Option<Lifetime>,
 }
 
 impl AppleBuilder {
@@ -43,16 +61,46 @@ impl AppleBuilder {
         self
     }
 
+    pub fn golden(mut self) -> Self {
+        self.is_golden = true;
+
+        self
+    }
+
+    /// Makes the apple rot, and disappear, once the lifetime is over.
+    pub fn with_lifetime(mut self, lifetime: Lifetime) -> Self {
+        self.lifetime = Some(lifetime);
+
+        self
+    }
+
     pub fn spawn(self, commands: &mut Commands, textures: &TextureAssets) -> Entity {
         let radian = self.angle.map_or(0.0, f32::to_radians);
         let position = Position(self.position.unwrap_or_default());
+        let color = if self.is_golden {
+            GOLDEN_APPLE_COLOR
+        } else {
+            Color::WHITE
+        };
 
         let mut commands = commands.spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color,
+                ..Default::default()
+            },
             texture: textures.apple.clone(),
             transform: Transform::from_rotation(Quat::from_rotation_z(radian)),
             ..Default::default()
         });
 
+        if self.is_golden {
+            commands.insert(GoldenApple);
+        }
+
+        if let Some(lifetime) = self.lifetime {
+            commands.insert(lifetime);
+        }
+
         commands
             .insert_bundle((Apple, RigidBody, position, Name::new("Apple")))
             .with_children(|parent| {
@@ -86,9 +134,6 @@ impl AppleBuilder {
                 },
             )));
         }
-        // if let Some(lifetime) = apple_spawner.apple_lifetime.clone() {
-        //     commands.entity(entity).insert(lifetime);
-        // }
 
         commands.id()
     }
@@ -99,20 +144,82 @@ pub struct AppleSpawner {
     pub timer: Timer,
     pub max_apples: usize,
     pub apple_lifetime: Option<Lifetime>,
+    /// Probability for a spawned apple to be a [`GoldenApple`].
+    pub golden_apple_chance: f64,
+}
+
+/// Seeded sequence of apples, drawn apart from the board so that every game started from the same seed gets the same
+/// apples in the same order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppleSequence(GameRng);
+
+impl AppleSequence {
+    pub const fn from_seed(seed: u64) -> Self {
+        Self(GameRng::from_seed(seed))
+    }
+
+    /// Draws the tile, the rotation and whether the next apple is golden, always with the same number of draws.
+    pub fn next_apple(&mut self, size: UVec2, golden_apple_chance: f64) -> (UVec2, f32, bool) {
+        let position = UVec2::new(self.0.gen_range(0..size.x), self.0.gen_range(0..size.y));
+        let angle = self.0.gen_range(0.0..360.0);
+        // unlike `gen_bool`, always draws, even for a certain or an impossible golden apple
+        let is_golden = self.0.gen::<f64>() < golden_apple_chance;
+
+        (position, angle, is_golden)
+    }
 }
 
 pub struct ApplePlugin;
 
 impl Plugin for ApplePlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(
-            spawn_apple
-                .run_if_resource_exists::<AppleSpawner>()
-                .run_if_resource_exists::<TileMap>()
-                .before(GameSystem::Movement),
-        )
-        .add_system(systems::update_timer::<Lifetime>)
-        .add_system(explode_apple);
+        app.add_event::<AppleExpired>()
+            .add_system(
+                spawn_apple
+                    .run_if_resource_exists::<AppleSpawner>()
+                    .run_if_resource_exists::<TileMap>()
+                    .label(GameSystem::SpawnApple)
+                    .before(GameSystem::Movement),
+            )
+            .add_system(systems::update_timer::<Lifetime>)
+            .add_system(explode_apple);
+    }
+}
+
+/// Draws the apples from the sequence of a daily challenge if there is one, or from the game RNG otherwise.
+#[derive(SystemParam)]
+pub struct AppleDraw<'w, 's> {
+    rng: ResMut<'w, GameRng>,
+    apple_sequence: Option<ResMut<'w, AppleSequence>>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl AppleDraw<'_, '_> {
+    /// Returns the position, the angle and whether the next apple is golden, if there is a free tile left for it.
+    fn next_apple(&mut self, tiles: &[&Tile], size: UVec2, golden_apple_chance: f64) -> Option<(UVec2, f32, bool)> {
+        if let Some(apple_sequence) = &mut self.apple_sequence {
+            let (target, angle, is_golden) = apple_sequence.next_apple(size, golden_apple_chance);
+
+            // an apple drawn on an occupied tile goes to the closest free one
+            let closest = tiles.iter().min_by_key(|tile| {
+                let offset = tile.position().as_ivec2() - target.as_ivec2();
+
+                offset.x.abs() + offset.y.abs()
+            })?;
+
+            return Some((closest.position(), angle, is_golden));
+        }
+
+        if tiles.is_empty() {
+            return None;
+        }
+
+        Some((
+            tiles[self.rng.gen_range(0..tiles.len())].position(),
+            self.rng.gen_range(0.0..360.0),
+            self.rng.gen_bool(golden_apple_chance.clamp(0.0, 1.0)),
+        ))
     }
 }
 
@@ -123,6 +230,7 @@ pub fn spawn_apple(
     mut apple_spawner: ResMut<AppleSpawner>,
     tile_map: Res<TileMap>,
     textures: Res<TextureAssets>,
+    mut apple_draw: AppleDraw,
 ) {
     apple_spawner.timer.tick(time.delta());
 
@@ -130,26 +238,73 @@ pub fn spawn_apple(
         let apple_count = query.iter().count();
 
         if apple_count < apple_spawner.max_apples {
-            let mut rng = rand::thread_rng();
-
             let tiles = tile_map.tiles().filter(|tile| tile.is_empty()).collect::<Vec<_>>();
+            let size = UVec2::new(tile_map.width(), tile_map.height());
 
-            let position = tiles[rng.gen_range(0..tiles.len())].position();
-            let angle = rng.gen_range(0.0..360.0);
+            let Some((position, angle, is_golden)) =
+                apple_draw.next_apple(&tiles, size, apple_spawner.golden_apple_chance)
+            else {
+                return;
+            };
 
-            AppleBuilder::default()
+            let mut apple_builder = AppleBuilder::default()
                 .with_position(IVec3::new(position.x as i32, position.y as i32, 1))
                 .with_angle(angle)
-                .animate()
-                .spawn(&mut commands, &*textures);
+                .animate();
+
+            if is_golden {
+                apple_builder = apple_builder.golden();
+            }
+
+            if let Some(lifetime) = apple_spawner.apple_lifetime.clone() {
+                apple_builder = apple_builder.with_lifetime(lifetime);
+            }
+
+            apple_builder.spawn(&mut commands, &*textures);
         }
     }
 }
 
-pub fn explode_apple(mut commands: Commands, mut query: Query<(Entity, &mut Lifetime), With<Apple>>) {
-    query.for_each_mut(|(entity, lifetime)| {
+pub fn explode_apple(
+    mut commands: Commands,
+    mut burst_writer: EventWriter<ParticleBurst>,
+    mut sound_writer: EventWriter<SoundEffect>,
+    mut expired_writer: EventWriter<AppleExpired>,
+    mut query: Query<(Entity, &mut Lifetime, &Transform), With<Apple>>,
+) {
+    query.for_each_mut(|(entity, lifetime, transform)| {
         if lifetime.just_finished() {
             commands.entity(entity).despawn_recursive();
+
+            burst_writer.send(ParticleBurst {
+                config: ParticleConfig::apple_burst(),
+                translation: transform.translation.truncate(),
+                count: 24,
+            });
+            sound_writer.send(SoundEffect::AppleExpire);
+            expired_writer.send(AppleExpired);
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::AppleSequence;
+
+    #[test]
+    fn it_draws_the_same_tiles_whatever_the_golden_apples() {
+        let mut always_golden = AppleSequence::from_seed(7);
+        let mut never_golden = AppleSequence::from_seed(7);
+        let size = UVec2::new(20, 10);
+
+        for _ in 0..16 {
+            let (position, angle, is_golden) = always_golden.next_apple(size, 1.0);
+
+            assert!(is_golden);
+            assert!(position.x < size.x && position.y < size.y);
+            assert_eq!(never_golden.next_apple(size, 0.0), (position, angle, false));
+        }
+    }
+}
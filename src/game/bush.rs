@@ -1,16 +1,79 @@
-use std::{f32::consts::PI, time::Duration};
+use std::time::Duration;
 
-use bevy::{prelude::*, utils::tracing::instrument::WithSubscriber};
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
 use bevy_tweening::{
     lens::{TransformRotateZLens, TransformScaleLens},
     Animator, EaseFunction, Tracks, Tween, TweeningType,
 };
+use iyes_loopless::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use super::{components::{Position, RigidBody}, wind::Windable};
+use super::components::{Lifetime, Position, RigidBody};
+use super::level::LevelEntity;
+use super::particles::{ParticleBurst, ParticleConfig};
+use super::power_up::{Ghost, TimedEffect};
+use super::snake::SnakeEvent;
+use super::tile_map::{Tile, TileMap};
+use super::wind::Windable;
+use super::GameSystem;
 use crate::assets::TextureAssets;
 
+const BUSH_SCALE: Vec3 = Vec3::new(1.15, 1.15, 1.0);
+const STUMP_COLOR: Color = Color::rgb(0.45, 0.3, 0.15);
+
+#[derive(Debug, Component)]
+pub struct Bush {
+    /// Rotation of the bush, in degrees.
+    pub angle: f32,
+}
+
+/// Lets a snake break through the bushes it runs into, one bush per charge.
+#[derive(Debug, Default, Component)]
+pub struct BushCutter {
+    pub charges: u32,
+}
+
+/// Regrowth settings of a level.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BushParameters {
+    /// Delay before a destroyed bush grows back from its stump, in seconds.
+    pub regrow_time: f32,
+}
+
+impl Default for BushParameters {
+    fn default() -> Self {
+        Self { regrow_time: 15.0 }
+    }
+}
+
+/// Requests the destruction of a bush, leaving a [`Stump`] behind.
+#[derive(Debug)]
+pub struct DestroyBush(pub Entity);
+
+/// Remains of a destroyed bush. It grows back once its timer is finished and its tile is free.
 #[derive(Debug, Component)]
-pub struct Bush;
+pub struct Stump {
+    regrow: Option<Timer>,
+    angle: f32,
+}
+
+impl Stump {
+    /// Rotation of the destroyed bush, in degrees.
+    pub const fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    pub const fn regrow_timer(&self) -> Option<&Timer> {
+        self.regrow.as_ref()
+    }
+}
+
+/// Bush being shattered, despawned at the end of its [`Lifetime`].
+#[derive(Debug, Component)]
+struct Shattering;
 
 #[derive(Default)]
 pub struct BushBuilder {
@@ -39,16 +102,22 @@ impl BushBuilder {
     }
 
     pub fn spawn(self, commands: &mut Commands, textures: &TextureAssets) -> Entity {
-        let radian = self.angle.map_or(0.0, f32::to_radians);
+        let angle = self.angle.unwrap_or_default();
+        let radian = angle.to_radians();
         let translation = self.position.unwrap_or_default().as_vec3();
 
         let mut commands = commands.spawn_bundle(SpatialBundle {
-            transform: Transform::from_rotation(Quat::from_rotation_z(radian)).with_scale(Vec3::new(1.15, 1.15, 1.0)),
+            transform: Transform::from_rotation(Quat::from_rotation_z(radian)).with_scale(BUSH_SCALE),
             ..Default::default()
         });
 
         commands
-            .insert_bundle((Bush, RigidBody, Windable, Name::new("Bush")))
+            .insert_bundle((
+                Bush { angle },
+                RigidBody,
+                Windable { rest_scale: BUSH_SCALE },
+                Name::new("Bush"),
+            ))
             .with_children(|parent| {
                 let mut lower_bush = parent.spawn_bundle(SpriteBundle {
                     texture: textures.bush_lower.clone(),
@@ -131,8 +200,174 @@ pub struct BushPlugin;
 
 impl Plugin for BushPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(animate_bush);
+        app.add_event::<DestroyBush>()
+            .add_system(cut_bushes.after(GameSystem::Death))
+            .add_system(destroy_bushes)
+            .add_system(remove_shattered_bushes)
+            .add_system(regrow_bushes.run_if_resource_exists::<TileMap>());
+    }
+}
+
+/// Destroys the bushes run into by snakes which still have [`BushCutter`] charges, ghosts going through them instead.
+fn cut_bushes(
+    mut snake_reader: EventReader<SnakeEvent>,
+    mut destroy_writer: EventWriter<DestroyBush>,
+    mut cutter_query: Query<&mut BushCutter, Without<TimedEffect<Ghost>>>,
+    bush_query: Query<(), With<Bush>>,
+) {
+    for event in snake_reader.iter() {
+        let (snake, entity) = match event {
+            SnakeEvent::HeadCollide { snake, entity } => (snake, entity),
+            SnakeEvent::Grow { .. } => continue,
+        };
+
+        if !bush_query.contains(*entity) {
+            continue;
+        }
+
+        if let Ok(mut cutter) = cutter_query.get_mut(*snake) {
+            if cutter.charges > 0 {
+                cutter.charges -= 1;
+
+                destroy_writer.send(DestroyBush(*entity));
+            }
+        }
+    }
+}
+
+fn destroy_bushes(
+    mut commands: Commands,
+    mut destroy_reader: EventReader<DestroyBush>,
+    mut burst_writer: EventWriter<ParticleBurst>,
+    bush_query: Query<(&Bush, &Position, &Transform)>,
+    parameters: Option<Res<BushParameters>>,
+    textures: Res<TextureAssets>,
+) {
+    // the same bush may be requested to be destroyed several times in a row
+    let mut destroyed = HashSet::default();
+
+    for DestroyBush(entity) in destroy_reader.iter() {
+        let (bush, position, transform) = match bush_query.get(*entity) {
+            Ok(bush) if destroyed.insert(*entity) => bush,
+            _ => continue,
+        };
+
+        commands
+            .entity(*entity)
+            .remove::<Bush>()
+            .remove::<RigidBody>()
+            .remove::<Windable>()
+            .insert_bundle((
+                Shattering,
+                Lifetime::from_seconds(0.3),
+                Animator::new(Tween::new(
+                    EaseFunction::QuadraticIn,
+                    TweeningType::Once,
+                    Duration::from_millis(300),
+                    TransformScaleLens {
+                        start: transform.scale,
+                        end: Vec3::ZERO,
+                    },
+                )),
+            ));
+
+        burst_writer.send(ParticleBurst {
+            config: ParticleConfig::bush_shatter(textures.apple_leaf.clone()),
+            translation: transform.translation.truncate(),
+            count: 20,
+        });
+
+        let regrow = parameters
+            .as_ref()
+            .map(|parameters| Timer::from_seconds(parameters.regrow_time, false));
+
+        spawn_stump(&mut commands, &textures, position.0, bush.angle, regrow);
     }
 }
 
-fn animate_bush() {}
+/// Spawns the remains of a bush, which grow back once the `regrow` timer is finished.
+pub fn spawn_stump(
+    commands: &mut Commands,
+    textures: &TextureAssets,
+    position: IVec3,
+    angle: f32,
+    regrow: Option<Timer>,
+) -> Entity {
+    commands
+        .spawn_bundle(SpatialBundle::default())
+        .insert_bundle((
+            Stump { regrow, angle },
+            Position(position),
+            LevelEntity,
+            Name::new("Stump"),
+        ))
+        .with_children(|parent| {
+            // stay below the snake crawling over the stump
+            parent.spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: STUMP_COLOR,
+                    ..Default::default()
+                },
+                texture: textures.bush_lower.clone(),
+                transform: Transform::from_xyz(0.0, 0.0, -0.5)
+                    .with_rotation(Quat::from_rotation_z(angle.to_radians()))
+                    .with_scale(Vec3::new(0.5, 0.5, 1.0)),
+                ..Default::default()
+            });
+        })
+        .id()
+}
+
+fn remove_shattered_bushes(mut commands: Commands, query: Query<(Entity, &Lifetime), With<Shattering>>) {
+    query.for_each(|(entity, lifetime)| {
+        if lifetime.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    });
+}
+
+fn regrow_bushes(
+    mut commands: Commands,
+    mut stump_query: Query<(Entity, &mut Stump, &Position)>,
+    tile_map: Res<TileMap>,
+    textures: Res<TextureAssets>,
+    time: Res<Time>,
+) {
+    stump_query.for_each_mut(|(entity, mut stump, position)| {
+        let angle = stump.angle;
+        let Some(timer) = stump.regrow.as_mut() else {
+            return;
+        };
+
+        if !timer.tick(time.delta()).finished() {
+            return;
+        }
+
+        // keep waiting while something stands on the stump
+        let is_free = tile_map.tile_under(position.xy()).is_some_and(Tile::is_empty);
+
+        if !is_free {
+            return;
+        }
+
+        commands.entity(entity).despawn_recursive();
+
+        let bush = BushBuilder::default()
+            .with_position(position.0)
+            .with_angle(angle)
+            .spawn(&mut commands, &textures);
+
+        commands.entity(bush).insert_bundle((
+            LevelEntity,
+            Animator::new(Tween::new(
+                EaseFunction::BackOut,
+                TweeningType::Once,
+                Duration::from_millis(600),
+                TransformScaleLens {
+                    start: Vec3::ZERO,
+                    end: BUSH_SCALE,
+                },
+            )),
+        ));
+    });
+}
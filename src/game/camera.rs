@@ -0,0 +1,156 @@
+use bevy::ecs::system::SystemParam;
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use super::components::Position;
+use super::snake::Snake;
+use super::tile_map::{TileMap, TileMapOptions};
+use super::GameStage;
+
+/// Camera showing the tile map, either entirely or by following its [`CameraTarget`] when tiles would get too small
+/// to fit the whole map in the window.
+#[derive(Debug, Default, Component)]
+pub struct GameCamera {
+    last_target_position: Option<IVec2>,
+}
+
+/// Marks the snake followed by the [`GameCamera`] when the tile map does not fit on screen.
+#[derive(Debug, Component)]
+pub struct CameraTarget;
+
+#[derive(Debug)]
+pub struct CameraOptions {
+    /// Extra room kept around the tile map when it fits on screen.
+    pub margin: f32,
+    /// Smallest tile size before the camera starts following its target instead.
+    pub min_tile_size: f32,
+    /// Fraction of the visible area in which the target can move without moving the camera.
+    pub dead_zone: Vec2,
+    /// How fast the camera catches up with its target.
+    pub smoothing: f32,
+}
+
+impl Default for CameraOptions {
+    fn default() -> Self {
+        Self {
+            margin: 1.05,
+            min_tile_size: 64.0,
+            dead_zone: Vec2::splat(0.4),
+            smoothing: 5.0,
+        }
+    }
+}
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraOptions>().add_system_to_stage(
+            GameStage::Transform,
+            update_camera
+                .run_if_resource_exists::<TileMap>()
+                .run_if_resource_exists::<TileMapOptions>(),
+        );
+    }
+}
+
+/// Returns the camera scale required to show an area of the given size in the window.
+pub fn fit_scale(size: Vec2, window: &Window, margin: f32) -> f32 {
+    (size.x * margin / window.width()).max(size.y * margin / window.height())
+}
+
+/// Head of the snake followed by the camera.
+#[derive(SystemParam)]
+struct Target<'w, 's> {
+    snakes: Query<'w, 's, &'static Snake, With<CameraTarget>>,
+    positions: Query<'w, 's, &'static Position>,
+}
+
+impl Target<'_, '_> {
+    fn position(&self) -> Option<IVec2> {
+        self.snakes
+            .iter()
+            .next()
+            .and_then(|snake| self.positions.get(snake.fragments[0]).ok())
+            .map(|position| position.0.xy())
+    }
+}
+
+fn update_camera(
+    mut camera_query: Query<(&mut Transform, &mut GameCamera)>,
+    target: Target,
+    windows: Res<Windows>,
+    time: Res<Time>,
+    tile_map: Res<TileMap>,
+    mut tile_map_options: ResMut<TileMapOptions>,
+    camera_options: Res<CameraOptions>,
+) {
+    let Some(window) = windows.get_primary() else {
+        return;
+    };
+
+    // derive the tile size from the space available in the window
+    let tile_count = Vec2::new(tile_map.width() as f32, tile_map.height() as f32);
+    let tile_size = 1.0 / fit_scale(tile_count, window, camera_options.margin);
+    let is_following = tile_size < camera_options.min_tile_size;
+    let tile_size = tile_size.max(camera_options.min_tile_size);
+
+    if (tile_map_options.tile_size - tile_size).abs() > f32::EPSILON {
+        tile_map_options.tile_size = tile_size;
+    }
+
+    let map_size = tile_count * tile_map_options.tile_size;
+    let map_min = tile_map_options.offset(&tile_map) - tile_map_options.tile_size / 2.0;
+    let map_center = map_min + map_size / 2.0;
+
+    let target_position = target.position();
+
+    camera_query.for_each_mut(|(mut transform, mut camera)| {
+        if !is_following {
+            transform.translation = map_center.extend(transform.translation.z);
+            camera.last_target_position = None;
+
+            return;
+        }
+
+        let half_view_size = Vec2::new(window.width(), window.height()) / 2.0;
+        let mut translation = transform.translation.xy();
+
+        if let Some(target_position) = target_position {
+            // the target wrapped around the tile map: move the camera along so it does not sweep the whole map
+            if let Some(last_target_position) = camera.last_target_position {
+                let delta = target_position - last_target_position;
+
+                if delta.x.abs() > 1 {
+                    translation.x += map_size.x * delta.x.signum() as f32;
+                }
+                if delta.y.abs() > 1 {
+                    translation.y += map_size.y * delta.y.signum() as f32;
+                }
+            }
+
+            camera.last_target_position = Some(target_position);
+
+            let target = tile_map_options.tile_to_world(&tile_map, target_position);
+            let dead_zone = half_view_size * camera_options.dead_zone;
+            let desired = translation.clamp(target - dead_zone, target + dead_zone);
+
+            translation = translation.lerp(desired, 1.0 - (-camera_options.smoothing * time.delta_seconds()).exp());
+        }
+
+        // never show what lies beyond the edges of the tile map
+        for axis in 0..2 {
+            translation[axis] = if map_size[axis] <= half_view_size[axis] * 2.0 {
+                map_center[axis]
+            } else {
+                translation[axis].clamp(
+                    map_min[axis] + half_view_size[axis],
+                    map_min[axis] + map_size[axis] - half_view_size[axis],
+                )
+            };
+        }
+
+        transform.translation = translation.extend(transform.translation.z);
+    });
+}
@@ -0,0 +1,407 @@
+use std::collections::HashMap;
+
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use iyes_loopless::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::animation::GameOver;
+use super::apple::AppleSpawner;
+use super::components::Lifetime;
+use super::level::Level;
+use super::score::Score;
+use super::snake::{Dead, MoveTimer, Snake};
+use crate::assets::AssetCollection;
+use crate::states::{AppScreen, GameStatus};
+use crate::storage;
+use crate::systems;
+
+pub const CAMPAIGN: &str = "campaign/main.campaign.json";
+pub const MAX_STARS: usize = 3;
+
+const PROGRESS_FILE: &str = "campaign.json";
+
+/// Objective of a campaign level, each one met earning a star.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Goal {
+    /// Grow the snake to the given number of fragments.
+    Length(usize),
+    /// Eat the given number of apples.
+    Apples(u32),
+    /// Stay alive for the given number of seconds.
+    Survive(f32),
+}
+
+impl Goal {
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Length(_) => "Reach length",
+            Self::Apples(_) => "Eat apples",
+            Self::Survive(_) => "Survive",
+        }
+    }
+
+    /// Returns how far the player is toward the goal, as `current/target`.
+    pub fn progress(self, progress: &GoalProgress) -> String {
+        match self {
+            Self::Length(length) => format!("{}/{length}", progress.length.min(length)),
+            Self::Apples(apples) => format!("{}/{apples}", progress.apples.min(apples)),
+            Self::Survive(seconds) => format!("{:.0}/{seconds:.0}s", progress.elapsed.min(seconds).floor()),
+        }
+    }
+
+    pub fn is_met(self, progress: &GoalProgress) -> bool {
+        match self {
+            Self::Length(length) => progress.length >= length,
+            Self::Apples(apples) => progress.apples >= apples,
+            Self::Survive(seconds) => progress.elapsed >= seconds,
+        }
+    }
+}
+
+/// What the player achieved so far in the campaign level being played.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct GoalProgress {
+    pub length: usize,
+    pub apples: u32,
+    /// Time spent alive, in seconds.
+    pub elapsed: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CampaignLevel {
+    pub name: String,
+    /// Level file, relative to the assets folder.
+    pub map: String,
+    pub goals: Vec<Goal>,
+    /// Number of apples on the map at once, overriding the one of a regular game.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_apples: Option<usize>,
+    /// Time before an apple rots, in seconds, overriding the one of a regular game.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apple_lifetime: Option<f32>,
+}
+
+impl CampaignLevel {
+    /// Returns the number of stars earned, one per goal met.
+    pub fn stars(&self, progress: &GoalProgress) -> u8 {
+        self.goals.iter().filter(|goal| goal.is_met(progress)).count() as u8
+    }
+
+    pub fn is_completed(&self, progress: &GoalProgress) -> bool {
+        self.goals.iter().all(|goal| goal.is_met(progress))
+    }
+}
+
+/// Levels of the campaign, played in order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypeUuid)]
+#[uuid = "a4c1e2b7-3f5d-4e08-9b6a-71d2c8e5f940"]
+pub struct Campaign {
+    pub levels: Vec<CampaignLevel>,
+}
+
+#[derive(Default)]
+pub struct CampaignLoader;
+
+impl AssetLoader for CampaignLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let campaign = serde_json::from_slice::<Campaign>(bytes)?;
+
+            if let Some(level) = campaign
+                .levels
+                .iter()
+                .find(|level| level.goals.is_empty() || level.goals.len() > MAX_STARS)
+            {
+                return Err(bevy::asset::Error::msg(format!(
+                    "level {} needs between 1 and {MAX_STARS} goals",
+                    level.name
+                )));
+            }
+
+            load_context.set_default_asset(LoadedAsset::new(campaign));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["campaign.json"]
+    }
+}
+
+pub struct CampaignAssets {
+    pub campaign: Handle<Campaign>,
+}
+
+impl FromWorld for CampaignAssets {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.get_resource::<AssetServer>().unwrap();
+
+        Self {
+            campaign: asset_server.load(CAMPAIGN),
+        }
+    }
+}
+
+/// Endless games stay playable without the campaign.
+impl AssetCollection for CampaignAssets {
+    const IS_REQUIRED: bool = false;
+
+    fn handles(&self) -> Vec<HandleUntyped> {
+        vec![self.campaign.clone_untyped()]
+    }
+}
+
+/// Best number of stars earned in each campaign level, by level file, persisted between two runs of the game.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CampaignProgress {
+    stars: HashMap<String, u8>,
+}
+
+impl CampaignProgress {
+    /// Loads the progress, starting the campaign over if it is missing or invalid.
+    pub fn load() -> Self {
+        storage::load(PROGRESS_FILE)
+            .unwrap_or_else(|e| {
+                warn!("ignoring campaign progress: {e}");
+
+                None
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = storage::save(PROGRESS_FILE, self) {
+            error!("cannot save campaign progress: {e}");
+        }
+    }
+
+    pub fn stars(&self, level: &CampaignLevel) -> u8 {
+        self.stars.get(&level.map).copied().unwrap_or_default()
+    }
+
+    /// Keeps the stars if they beat the best ones of the level, returning whether they did.
+    pub fn record(&mut self, level: &CampaignLevel, stars: u8) -> bool {
+        if stars <= self.stars(level) {
+            return false;
+        }
+
+        self.stars.insert(level.map.clone(), stars);
+
+        true
+    }
+
+    /// The first level is always unlocked, the next ones once a star is earned in the previous one.
+    pub fn is_unlocked(&self, campaign: &Campaign, index: usize) -> bool {
+        match index {
+            0 => !campaign.levels.is_empty(),
+            index => campaign
+                .levels
+                .get(index - 1)
+                .is_some_and(|previous| index < campaign.levels.len() && self.stars(previous) > 0),
+        }
+    }
+}
+
+/// Campaign level being played instead of the current level, kept across retries.
+pub struct CampaignRun {
+    pub campaign: Handle<Campaign>,
+    pub index: usize,
+    pub level: Handle<Level>,
+    pub progress: GoalProgress,
+    /// Stars earned once the level is over, whether the goals are all met or the snake died.
+    pub result: Option<u8>,
+}
+
+impl CampaignRun {
+    pub fn new(asset_server: &AssetServer, campaign: Handle<Campaign>, level: &CampaignLevel, index: usize) -> Self {
+        Self {
+            campaign,
+            index,
+            level: asset_server.load(level.map.as_str()),
+            progress: GoalProgress::default(),
+            result: None,
+        }
+    }
+
+    pub fn level<'a>(&self, campaigns: &'a Assets<Campaign>) -> Option<&'a CampaignLevel> {
+        campaigns
+            .get(&self.campaign)
+            .and_then(|campaign| campaign.levels.get(self.index))
+    }
+}
+
+pub struct CampaignPlugin;
+
+impl Plugin for CampaignPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Campaign>()
+            .init_asset_loader::<CampaignLoader>()
+            .init_resource::<CampaignAssets>()
+            .insert_resource(CampaignProgress::load())
+            .add_enter_system(AppScreen::InGame, reset_campaign_run)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppScreen::InGame)
+                    .run_if_resource_exists::<CampaignRun>()
+                    .with_system(configure_apple_spawner)
+                    .with_system(track_goals.run_in_state(GameStatus::Running))
+                    .with_system(end_level)
+                    .into(),
+            )
+            // the level is only kept while retrying
+            .add_enter_system(AppScreen::MainMenu, systems::despawn_resource::<CampaignRun>);
+    }
+}
+
+fn reset_campaign_run(campaign_run: Option<ResMut<CampaignRun>>) {
+    if let Some(mut campaign_run) = campaign_run {
+        campaign_run.progress = GoalProgress::default();
+        campaign_run.result = None;
+    }
+}
+
+/// Applies the apple pace of the level to the spawner created along with the game.
+fn configure_apple_spawner(
+    campaign_run: Res<CampaignRun>,
+    campaigns: Res<Assets<Campaign>>,
+    apple_spawner: Option<ResMut<AppleSpawner>>,
+) {
+    let (mut apple_spawner, level) = match (apple_spawner, campaign_run.level(&campaigns)) {
+        (Some(apple_spawner), Some(level)) if apple_spawner.is_added() => (apple_spawner, level),
+        _ => return,
+    };
+
+    if let Some(max_apples) = level.max_apples {
+        apple_spawner.max_apples = max_apples;
+    }
+
+    if let Some(seconds) = level.apple_lifetime {
+        apple_spawner.apple_lifetime = Some(Lifetime::from_seconds(seconds));
+    }
+}
+
+fn track_goals(
+    mut campaign_run: ResMut<CampaignRun>,
+    time: Res<Time>,
+    score: Option<Res<Score>>,
+    snake_query: Query<&Snake, Without<Dead>>,
+) {
+    if campaign_run.result.is_some() || snake_query.is_empty() {
+        return;
+    }
+
+    let progress = &mut campaign_run.progress;
+
+    progress.elapsed += time.delta_seconds();
+    progress.apples = score.map_or(0, |score| score.0);
+    progress.length = snake_query
+        .iter()
+        .map(|snake| snake.fragments.len())
+        .max()
+        .unwrap_or_default();
+}
+
+/// Ends the level once every goal is met, or once the snake is done dying, saving the stars earned.
+fn end_level(
+    mut commands: Commands,
+    mut campaign_run: ResMut<CampaignRun>,
+    mut progress: ResMut<CampaignProgress>,
+    mut game_over_reader: EventReader<GameOver>,
+    campaigns: Res<Assets<Campaign>>,
+    mut timer_query: Query<&mut MoveTimer>,
+) {
+    let is_dead = game_over_reader.iter().last().is_some();
+    let Some(level) = campaign_run.level(&campaigns) else {
+        return;
+    };
+
+    if campaign_run.result.is_some() {
+        return;
+    }
+
+    if level.is_completed(&campaign_run.progress) {
+        // the snakes stand still under the overlay instead of running into something
+        timer_query.for_each_mut(|mut timer| timer.pause());
+
+        commands.insert_resource(NextState(GameStatus::GameOver));
+    } else if !is_dead {
+        return;
+    }
+
+    let stars = level.stars(&campaign_run.progress);
+
+    if progress.record(level, stars) {
+        progress.save();
+    }
+
+    campaign_run.result = Some(stars);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Campaign, CampaignLevel, CampaignProgress, Goal, GoalProgress};
+
+    fn level(map: &str) -> CampaignLevel {
+        CampaignLevel {
+            name: map.to_string(),
+            map: map.to_string(),
+            goals: vec![Goal::Length(6), Goal::Apples(5), Goal::Survive(30.0)],
+            max_apples: None,
+            apple_lifetime: None,
+        }
+    }
+
+    #[test]
+    fn it_parses_bundled_campaign() {
+        let path = format!("{}/assets/{}", env!("CARGO_MANIFEST_DIR"), super::CAMPAIGN);
+        let campaign = serde_json::from_slice::<Campaign>(&std::fs::read(path).unwrap()).unwrap();
+
+        for level in &campaign.levels {
+            let path = format!("{}/assets/{}", env!("CARGO_MANIFEST_DIR"), level.map);
+
+            assert!(crate::game::level::Level::load(&path).is_ok(), "{} is a valid level", level.map);
+            assert!((1..=super::MAX_STARS).contains(&level.goals.len()));
+        }
+    }
+
+    #[test]
+    fn it_earns_a_star_per_goal_met() {
+        let level = level("first.json");
+        let progress = GoalProgress {
+            length: 7,
+            apples: 5,
+            elapsed: 12.0,
+        };
+
+        assert_eq!(level.stars(&progress), 2);
+        assert!(!level.is_completed(&progress));
+        assert_eq!(Goal::Survive(30.0).progress(&progress), "12/30s");
+    }
+
+    #[test]
+    fn it_unlocks_levels_in_order() {
+        let campaign = Campaign {
+            levels: vec![level("first.json"), level("second.json"), level("third.json")],
+        };
+        let mut progress = CampaignProgress::default();
+
+        assert!(progress.is_unlocked(&campaign, 0));
+        assert!(!progress.is_unlocked(&campaign, 1));
+
+        assert!(progress.record(&campaign.levels[0], 1));
+        assert!(!progress.record(&campaign.levels[0], 1));
+        assert!(progress.is_unlocked(&campaign, 1));
+        assert!(!progress.is_unlocked(&campaign, 2));
+        assert!(!progress.is_unlocked(&campaign, 3));
+    }
+}
@@ -3,9 +3,11 @@ use std::f32::consts::PI;
 use bevy::math::Vec3Swizzles;
 use bevy::prelude::*;
 use derive_more::Display;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Display, Default, Copy, Clone, PartialEq, Eq, Component, Reflect)]
+#[derive(Debug, Display, Default, Copy, Clone, PartialEq, Eq, Component, Reflect, Serialize, Deserialize)]
 #[reflect_value()]
+#[serde(rename_all = "kebab-case")]
 pub enum Direction {
     #[default]
     Up,
@@ -7,5 +7,5 @@ pub mod rigid_body;
 pub use direction::Direction;
 pub use lifetime::Lifetime;
 pub use number_generator::NumberGenerator;
-pub use position::Position;
+pub use position::{Detached, Position, Unbounded};
 pub use rigid_body::RigidBody;
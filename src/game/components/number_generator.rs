@@ -3,19 +3,21 @@ use std::ops::Range;
 use bevy::prelude::*;
 use rand::distributions::uniform::SampleUniform;
 use rand::distributions::{Distribution, Standard};
-use rand::rngs::SmallRng;
-use rand::{Rng, SeedableRng};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Component)]
+use crate::game::rng::GameRng;
+
+#[derive(Debug, Clone, Component, Serialize, Deserialize)]
 pub struct NumberGenerator<T> {
-    rng: SmallRng,
+    rng: GameRng,
     range: Option<Range<T>>,
 }
 
 impl<T> Default for NumberGenerator<T> {
     fn default() -> Self {
         Self {
-            rng: SmallRng::from_entropy(),
+            rng: GameRng::from_entropy(),
             range: None,
         }
     }
@@ -39,18 +41,11 @@ where
     //     self
     // }
 
-    // pub fn from_seed(seed: u64) -> Self {
-    //     Self {
-    //         rng: SmallRng::seed_from_u64(seed),
-    //         ..Default::default()
-    //     }
-    // }
-
-    // pub fn with_seed(mut self, seed: u64) -> Self {
-    //     self.rng = SmallRng::seed_from_u64(seed);
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = GameRng::from_seed(seed);
 
-    //     self
-    // }
+        self
+    }
 
     pub fn generate(&mut self) -> T {
         if let Some(range) = &self.range {
@@ -7,6 +7,14 @@ use derive_more::{Deref, DerefMut};
 #[reflect(Component)]
 pub struct Position(pub IVec3);
 
+/// Marks positioned entities allowed to leave the tile map instead of wrapping around its edges.
+#[derive(Debug, Default, Copy, Clone, Component)]
+pub struct Unbounded;
+
+/// Marks positioned entities whose transform is animated freely instead of following their tile, e.g. while dying.
+#[derive(Debug, Default, Copy, Clone, Component)]
+pub struct Detached;
+
 impl Add<IVec3> for Position {
     type Output = Self;
 
@@ -0,0 +1,401 @@
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use derive_more::Display;
+use iyes_loopless::prelude::*;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use super::apple::AppleSequence;
+use super::bush::BushParameters;
+use super::components::Direction;
+use super::level::{Level, LevelTile, SnakeSpawn, Terrain, TerrainTile, TileEntity};
+use super::rng::GameRng;
+use super::score::Score;
+use super::GameSystem;
+use crate::states::{AppScreen, GameStatus};
+use crate::storage;
+use crate::systems;
+
+const RESULTS_FILE: &str = "daily.json";
+const CODE_PREFIX: &str = "SNAKE";
+/// Mixed into the day number, so that the seed of a day looks nothing like the one of the day before.
+const SEED_SALT: u64 = 0x5EED_DA11_C4A1_1E46;
+/// Mixed into the seed of the challenge, so that the apples are not drawn from the same numbers as the map.
+const APPLE_SEED_SALT: u64 = 0xA991_E5EE_D5A1_7000;
+const BUSH_CHANCE: f64 = 0.08;
+
+/// Day of a daily challenge, in UTC so that every player gets the same challenge at the same time.
+#[derive(Debug, Display, Copy, Clone, PartialEq, Eq, Hash)]
+#[display(fmt = "{year:04}-{month:02}-{day:02}")]
+pub struct ChallengeDate {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl ChallengeDate {
+    pub fn today() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+
+        Self::from_days((seconds / 86_400) as i64)
+    }
+
+    /// Converts a number of days since 1970-01-01 to a date of the proleptic Gregorian calendar.
+    pub const fn from_days(days: i64) -> Self {
+        // counts from 0000-03-01 so that the leap day ends the year
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        // the day within the era is never negative, and neither is anything derived from it
+        let day_of_era = days.rem_euclid(146_097) as u32;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+        let year = year_of_era as i64 + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        Self {
+            year: year as i32,
+            month,
+            day,
+        }
+    }
+
+    /// Returns the number of days since 1970-01-01, the inverse of [`ChallengeDate::from_days`].
+    pub const fn days(self) -> i64 {
+        let year = self.year as i64 - if self.month <= 2 { 1 } else { 0 };
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let month = self.month as i64;
+        let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    /// Returns the seed of the challenge of the day, generating both its map and its apples.
+    pub fn seed(self) -> u32 {
+        // leaves the days since 1970 as they are, the ones before wrapping around to the end of the range
+        let days = self.days().rem_euclid(i64::MAX) as u64;
+
+        GameRng::from_seed(days ^ SEED_SALT).next_u32()
+    }
+
+    /// Parses a date written as `YYYYMMDD`, as found in a share code.
+    fn parse_compact(value: &str) -> Option<Self> {
+        if value.len() != 8 || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+
+        let date = Self {
+            year: value[..4].parse().ok()?,
+            month: value[4..6].parse().ok()?,
+            day: value[6..].parse().ok()?,
+        };
+
+        // rejects the days which do not exist, such as 2023-02-30
+        (Self::from_days(date.days()) == date).then_some(date)
+    }
+}
+
+/// Result of a daily challenge, shared as a short code such as `SNAKE-20261019-9F3A12C0-42`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DailyResult {
+    pub date: ChallengeDate,
+    pub seed: u32,
+    pub score: u32,
+}
+
+impl DailyResult {
+    pub fn new(date: ChallengeDate, score: u32) -> Self {
+        Self {
+            date,
+            seed: date.seed(),
+            score,
+        }
+    }
+
+    pub fn code(&self) -> String {
+        let date = self.date;
+
+        format!(
+            "{CODE_PREFIX}-{:04}{:02}{:02}-{:08X}-{}",
+            date.year, date.month, date.day, self.seed, self.score
+        )
+    }
+
+    /// Reads a share code back, rejecting it if its seed is not the one of its date.
+    pub fn from_code(code: &str) -> Option<Self> {
+        let mut parts = code.trim().split('-');
+
+        if parts.next()? != CODE_PREFIX {
+            return None;
+        }
+
+        let date = ChallengeDate::parse_compact(parts.next()?)?;
+        let seed = u32::from_str_radix(parts.next()?, 16).ok()?;
+        let score = parts.next()?.parse().ok()?;
+
+        if parts.next().is_some() || seed != date.seed() {
+            return None;
+        }
+
+        Some(Self { date, seed, score })
+    }
+}
+
+/// Scores of the daily challenges played, by date, persisted between two runs of the game.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyResults {
+    scores: HashMap<String, u32>,
+}
+
+impl DailyResults {
+    /// Loads the results, starting over if they are missing or invalid.
+    pub fn load() -> Self {
+        storage::load(RESULTS_FILE)
+            .unwrap_or_else(|e| {
+                warn!("ignoring daily challenge results: {e}");
+
+                None
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = storage::save(RESULTS_FILE, self) {
+            error!("cannot save daily challenge results: {e}");
+        }
+    }
+
+    pub fn result(&self, date: ChallengeDate) -> Option<DailyResult> {
+        self.scores
+            .get(&date.to_string())
+            .map(|score| DailyResult::new(date, *score))
+    }
+
+    /// Uses up the scored attempt of the day, returning whether it was still available.
+    ///
+    /// The attempt is spent as soon as it starts, so that leaving a bad game does not earn another one.
+    pub fn begin(&mut self, date: ChallengeDate) -> bool {
+        if self.scores.contains_key(&date.to_string()) {
+            return false;
+        }
+
+        self.scores.insert(date.to_string(), 0);
+
+        true
+    }
+
+    pub fn finish(&mut self, date: ChallengeDate, score: u32) {
+        self.scores.insert(date.to_string(), score);
+    }
+}
+
+/// Daily challenge being played instead of the current level, kept across practice retries.
+pub struct DailyRun {
+    pub date: ChallengeDate,
+    pub seed: u32,
+    pub level: Handle<Level>,
+    /// Whether the game being played is the scored attempt of the day rather than a practice one.
+    pub is_scored: bool,
+}
+
+impl DailyRun {
+    /// Generates the challenge of the given day, adding its level to the assets.
+    pub fn new(levels: &mut Assets<Level>, date: ChallengeDate) -> Self {
+        let seed = date.seed();
+
+        Self {
+            date,
+            seed,
+            level: levels.add(generate_level(seed)),
+            is_scored: false,
+        }
+    }
+
+    /// Returns whether the game can be saved, loaded or rewound, which would let the scored attempt of a daily
+    /// challenge be played again.
+    pub fn allows_retries(daily_run: Option<&Self>) -> bool {
+        !daily_run.is_some_and(|daily_run| daily_run.is_scored)
+    }
+
+    /// Returns the apples of the challenge, starting over on every attempt.
+    pub fn apple_sequence(&self) -> AppleSequence {
+        AppleSequence::from_seed(u64::from(self.seed) ^ APPLE_SEED_SALT)
+    }
+}
+
+/// Generates a level from a seed, the same seed always giving the same level.
+pub fn generate_level(seed: u32) -> Level {
+    let mut rng = GameRng::from_seed(u64::from(seed));
+    let width = rng.gen_range(14..=22);
+    let height = rng.gen_range(8..=12);
+    let direction = [Direction::Up, Direction::Down, Direction::Left, Direction::Right][rng.gen_range(0..4)];
+    let snake = SnakeSpawn {
+        x: width / 2,
+        y: height / 2,
+        direction,
+        length: NonZeroUsize::new(4).unwrap(),
+    };
+
+    let mut terrain = Vec::new();
+
+    for _ in 0..rng.gen_range(1..=3) {
+        let patch = if rng.gen_bool(0.5) { Terrain::Dirt } else { Terrain::Sand };
+        let (center_x, center_y) = (rng.gen_range(0..width), rng.gen_range(0..height));
+        let radius = rng.gen_range(1..=3);
+
+        for y in center_y.saturating_sub(radius)..(center_y + radius + 1).min(height) {
+            for x in center_x.saturating_sub(radius)..(center_x + radius + 1).min(width) {
+                if x.abs_diff(center_x) + y.abs_diff(center_y) <= radius
+                    && !terrain.iter().any(|tile: &TerrainTile| tile.x == x && tile.y == y)
+                {
+                    terrain.push(TerrainTile { terrain: patch, x, y });
+                }
+            }
+        }
+    }
+
+    let mut tiles = Vec::new();
+
+    for y in 0..height {
+        for x in 0..width {
+            // the row and the column of the snake are left clear, so that the first moves are safe
+            if x == snake.x || y == snake.y {
+                continue;
+            }
+
+            if rng.gen_bool(BUSH_CHANCE) {
+                tiles.push(LevelTile {
+                    entity: TileEntity::Bush,
+                    x,
+                    y,
+                    angle: Some(rng.gen_range(0.0..360.0)),
+                });
+            }
+        }
+    }
+
+    Level {
+        width,
+        height,
+        tiles,
+        terrain,
+        snake: Some(snake),
+        wind: None,
+        bushes: Some(BushParameters::default()),
+    }
+}
+
+pub struct DailyPlugin;
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DailyResults::load())
+            .add_enter_system(AppScreen::InGame, begin_attempt.run_if_resource_exists::<DailyRun>())
+            .add_enter_system(
+                GameStatus::GameOver,
+                record_daily_score
+                    .run_if_resource_exists::<DailyRun>()
+                    .label(GameSystem::RecordScore),
+            )
+            // the challenge is only kept while retrying
+            .add_enter_system(AppScreen::MainMenu, systems::despawn_resource::<DailyRun>);
+    }
+}
+
+/// Scores the game if it is the first one of the day, the next ones being practice.
+fn begin_attempt(mut daily_run: ResMut<DailyRun>, mut results: ResMut<DailyResults>) {
+    daily_run.is_scored = results.begin(daily_run.date);
+
+    if daily_run.is_scored {
+        results.save();
+    }
+}
+
+fn record_daily_score(daily_run: Res<DailyRun>, score: Option<Res<Score>>, mut results: ResMut<DailyResults>) {
+    if !daily_run.is_scored {
+        return;
+    }
+
+    let result = DailyResult::new(daily_run.date, score.map_or(0, |score| score.0));
+
+    results.finish(daily_run.date, result.score);
+    results.save();
+
+    info!("daily challenge of {} over, share code: {}", result.date, result.code());
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{generate_level, ChallengeDate, DailyResult, DailyResults, DailyRun};
+
+    const DATE: ChallengeDate = ChallengeDate {
+        year: 2026,
+        month: 10,
+        day: 19,
+    };
+
+    #[test]
+    fn it_converts_days_to_dates() {
+        assert_eq!(ChallengeDate::from_days(0).to_string(), "1970-01-01");
+        assert_eq!(ChallengeDate::from_days(-1).to_string(), "1969-12-31");
+        assert_eq!(ChallengeDate::from_days(11_016).to_string(), "2000-02-29");
+        assert_eq!(ChallengeDate::from_days(20_745), DATE);
+        assert_eq!(DATE.days(), 20_745);
+    }
+
+    #[test]
+    fn it_generates_the_same_challenge_for_a_date() {
+        assert_eq!(DATE.seed(), ChallengeDate::from_days(20_745).seed());
+        assert_ne!(DATE.seed(), ChallengeDate::from_days(20_746).seed());
+        assert_eq!(generate_level(DATE.seed()), generate_level(DATE.seed()));
+    }
+
+    #[test]
+    fn it_disallows_retries_of_the_scored_attempt() {
+        let mut daily_run = DailyRun {
+            date: DATE,
+            seed: DATE.seed(),
+            level: Handle::default(),
+            is_scored: false,
+        };
+
+        assert!(DailyRun::allows_retries(None));
+        assert!(DailyRun::allows_retries(Some(&daily_run)));
+
+        daily_run.is_scored = true;
+        assert!(!DailyRun::allows_retries(Some(&daily_run)));
+    }
+
+    #[test]
+    fn it_reads_back_its_share_code() {
+        let result = DailyResult::new(DATE, 42);
+        let code = result.code();
+
+        assert!(code.starts_with("SNAKE-20261019-"));
+        assert_eq!(DailyResult::from_code(&code), Some(result));
+        assert_eq!(DailyResult::from_code(&code.replace("20261019", "20261020")), None);
+        assert_eq!(DailyResult::from_code("SNAKE-20260230-00000000-1"), None);
+    }
+
+    #[test]
+    fn it_scores_a_single_attempt_per_day() {
+        let mut results = DailyResults::default();
+
+        assert!(results.begin(DATE));
+        results.finish(DATE, 12);
+
+        assert!(!results.begin(DATE));
+        assert_eq!(results.result(DATE).map(|result| result.score), Some(12));
+    }
+}
@@ -0,0 +1,292 @@
+use std::f32::consts::PI;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_tweening::lens::{TransformRotateZLens, TransformScaleLens};
+use bevy_tweening::{Animator, EaseFunction, EaseMethod, Sequence, Tween, TweeningType};
+use iyes_loopless::prelude::*;
+
+use super::apple::Apple;
+use super::components::{Direction, Position};
+use super::snake::{Dead, Snake, SnakeFragment};
+use super::tile_map::TileMapOptions;
+use super::GameStage;
+use crate::assets::SnakeAtlas;
+
+/// Delay between two flicks of the tongue, in seconds.
+const TONGUE_FLICK_INTERVAL: f32 = 2.5;
+const MOUTH_OPEN_SCALE: Vec3 = Vec3::new(0.12, 0.3, 1.0);
+const MOUTH_CLOSED_SCALE: Vec3 = Vec3::new(0.12, 0.0, 1.0);
+const MOUTH_COLOR: Color = Color::rgb(0.35, 0.05, 0.1);
+const STAR_COLOR: Color = Color::rgb(1.0, 0.9, 0.2);
+const DIZZY_HEAD_COLOR: Color = Color::rgb(0.75, 0.8, 0.6);
+
+/// Head children are laid out in the space of the head texture, which faces left.
+const FORWARD: Vec3 = Vec3::NEG_X;
+
+#[derive(Debug, Component)]
+struct Tongue {
+    flick: Timer,
+}
+
+#[derive(Debug, Component)]
+struct Mouth {
+    is_open: bool,
+}
+
+/// Stars spinning above the head of a dead snake.
+#[derive(Debug, Component)]
+struct DizzyStars;
+
+pub struct ExpressionPlugin;
+
+impl Plugin for ExpressionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            setup_face
+                .run_if_resource_exists::<SnakeAtlas>()
+                .run_if_resource_exists::<TileMapOptions>(),
+        )
+        .add_system_set_to_stage(
+            GameStage::Transform,
+            SystemSet::new()
+                .with_system(flick_tongue)
+                .with_system(open_mouth)
+                .with_system(show_dizzy_face.run_if_resource_exists::<TileMapOptions>())
+                .with_system(hide_dizzy_face),
+        );
+    }
+}
+
+/// Gives a tongue and a mouth to the heads as soon as they are spawned.
+fn setup_face(
+    mut commands: Commands,
+    query: Query<(Entity, &SnakeFragment), Added<SnakeFragment>>,
+    snake_atlas: Res<SnakeAtlas>,
+    tile_map_options: Res<TileMapOptions>,
+) {
+    // the children are stretched to a tile like any other sprite, so only their scale sets their size
+    let mouth_edge = FORWARD * tile_map_options.tile_size / 2.0;
+
+    query.for_each(|(entity, fragment)| {
+        if !matches!(fragment, SnakeFragment::Head) {
+            return;
+        }
+
+        commands.entity(entity).with_children(|parent| {
+            if let Some(tongue) = &snake_atlas.tongue {
+                // the tongue fills the right half of its texture, with its tip on the right
+                parent
+                    .spawn_bundle(SpriteBundle {
+                        sprite: Sprite {
+                            flip_x: true,
+                            ..default()
+                        },
+                        texture: tongue.clone(),
+                        transform: Transform::from_translation(mouth_edge - Vec3::Z * 0.05)
+                            .with_scale(Vec3::new(0.0, 1.0, 1.0)),
+                        ..default()
+                    })
+                    .insert_bundle((
+                        Tongue {
+                            flick: Timer::from_seconds(TONGUE_FLICK_INTERVAL, true),
+                        },
+                        Name::new("Snake Tongue"),
+                    ));
+            }
+
+            parent
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: MOUTH_COLOR,
+                        ..default()
+                    },
+                    transform: Transform::from_translation(mouth_edge * 0.8 + Vec3::Z * 0.05)
+                        .with_scale(MOUTH_CLOSED_SCALE),
+                    ..default()
+                })
+                .insert_bundle((Mouth { is_open: false }, Name::new("Snake Mouth")));
+        });
+    });
+}
+
+fn flick_tongue(
+    mut commands: Commands,
+    mut tongue_query: Query<(Entity, &mut Tongue, &Parent)>,
+    mouth_query: Query<(&Mouth, &Parent)>,
+    dead_query: Query<(), With<Dead>>,
+    snake_query: Query<(Entity, &Snake)>,
+    time: Res<Time>,
+) {
+    tongue_query.for_each_mut(|(entity, mut tongue, parent)| {
+        if !tongue.flick.tick(time.delta()).just_finished() {
+            return;
+        }
+
+        let is_dead = snake_query.iter().any(|(snake_entity, snake)| {
+            snake.fragments.first() == Some(&parent.get()) && dead_query.contains(snake_entity)
+        });
+        let is_mouth_open = mouth_query
+            .iter()
+            .any(|(mouth, mouth_parent)| mouth_parent.get() == parent.get() && mouth.is_open);
+
+        if is_dead || is_mouth_open {
+            return;
+        }
+
+        commands.entity(entity).insert(Animator::new(Sequence::new([
+            Tween::new(
+                EaseFunction::QuadraticOut,
+                TweeningType::Once,
+                Duration::from_millis(120),
+                TransformScaleLens {
+                    start: Vec3::new(0.0, 1.0, 1.0),
+                    end: Vec3::ONE,
+                },
+            ),
+            Tween::new(
+                EaseFunction::QuadraticIn,
+                TweeningType::Once,
+                Duration::from_millis(120),
+                TransformScaleLens {
+                    start: Vec3::ONE,
+                    end: Vec3::new(0.0, 1.0, 1.0),
+                },
+            ),
+        ])));
+    });
+}
+
+/// Opens the mouth of the living snakes facing an apple, and closes it otherwise.
+fn open_mouth(
+    mut commands: Commands,
+    snake_query: Query<(&Snake, Option<&Dead>)>,
+    head_query: Query<(&Position, &Direction, &Children)>,
+    mut mouth_query: Query<(Entity, &mut Mouth, &Transform)>,
+    apple_query: Query<&Position, With<Apple>>,
+) {
+    snake_query.for_each(|(snake, dead)| {
+        let Some(Ok((position, direction, children))) = snake.fragments.first().map(|head| head_query.get(*head))
+        else {
+            return;
+        };
+
+        let ahead = position.0.truncate() + direction.to_ivec2();
+        let is_open = dead.is_none() && apple_query.iter().any(|apple| apple.0.truncate() == ahead);
+
+        let mut iter = mouth_query.iter_many_mut(children);
+
+        while let Some((entity, mut mouth, transform)) = iter.fetch_next() {
+            if mouth.is_open == is_open {
+                continue;
+            }
+
+            mouth.is_open = is_open;
+
+            let end = if is_open { MOUTH_OPEN_SCALE } else { MOUTH_CLOSED_SCALE };
+
+            commands.entity(entity).insert(Animator::new(Tween::new(
+                EaseFunction::BackOut,
+                TweeningType::Once,
+                Duration::from_millis(150),
+                TransformScaleLens {
+                    start: transform.scale,
+                    end,
+                },
+            )));
+        }
+    });
+}
+
+/// Makes the head of the snakes which just died look dizzy, with stars spinning above it.
+fn show_dizzy_face(
+    mut commands: Commands,
+    snake_query: Query<&Snake, Added<Dead>>,
+    mut head_query: Query<(&mut TextureAtlasSprite, &Children)>,
+    mut tongue_query: Query<&mut Visibility, With<Tongue>>,
+    tile_map_options: Res<TileMapOptions>,
+) {
+    let radius = tile_map_options.tile_size * 0.4;
+
+    snake_query.for_each(|snake| {
+        let head = match snake.fragments.first() {
+            Some(head) => *head,
+            None => return,
+        };
+
+        if let Ok((mut sprite, children)) = head_query.get_mut(head) {
+            sprite.color = DIZZY_HEAD_COLOR;
+
+            let mut iter = tongue_query.iter_many_mut(children);
+
+            while let Some(mut visibility) = iter.fetch_next() {
+                visibility.is_visible = false;
+            }
+        }
+
+        commands.entity(head).with_children(|parent| {
+            parent
+                .spawn_bundle(SpatialBundle::from_transform(Transform::from_xyz(0.0, 0.0, 0.2)))
+                .insert_bundle((
+                    Animator::new(Tween::new(
+                        EaseMethod::Linear,
+                        TweeningType::Loop,
+                        Duration::from_millis(1200),
+                        TransformRotateZLens {
+                            start: 0.0,
+                            end: 2.0 * PI,
+                        },
+                    )),
+                    DizzyStars,
+                    Name::new("Dizzy Stars"),
+                ))
+                .with_children(|parent| {
+                    for i in 0..3 {
+                        let angle = i as f32 * 2.0 * PI / 3.0;
+
+                        parent.spawn_bundle(SpriteBundle {
+                            sprite: Sprite {
+                                color: STAR_COLOR,
+                                ..default()
+                            },
+                            transform: Transform::from_translation(Vec3::new(angle.cos(), angle.sin(), 0.0) * radius)
+                                .with_rotation(Quat::from_rotation_z(PI / 4.0))
+                                .with_scale(Vec3::splat(0.12)),
+                            ..default()
+                        });
+                    }
+                });
+        });
+    });
+}
+
+/// Brings the face of the snakes back to normal once they are alive again, e.g. after a rewind.
+fn hide_dizzy_face(
+    mut commands: Commands,
+    revived: RemovedComponents<Dead>,
+    snake_query: Query<&Snake>,
+    mut head_query: Query<(&mut TextureAtlasSprite, &Children)>,
+    stars_query: Query<Entity, With<DizzyStars>>,
+    mut tongue_query: Query<&mut Visibility, With<Tongue>>,
+) {
+    for entity in revived.iter() {
+        let head = match snake_query.get(entity).ok().and_then(|snake| snake.fragments.first()) {
+            Some(head) => *head,
+            None => continue,
+        };
+
+        if let Ok((mut sprite, children)) = head_query.get_mut(head) {
+            sprite.color = Color::WHITE;
+
+            stars_query
+                .iter_many(children)
+                .for_each(|stars| commands.entity(stars).despawn_recursive());
+
+            let mut iter = tongue_query.iter_many_mut(children);
+
+            while let Some(mut visibility) = iter.fetch_next() {
+                visibility.is_visible = true;
+            }
+        }
+    }
+}
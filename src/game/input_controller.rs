@@ -1,14 +1,211 @@
 use bevy::prelude::*;
 use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
 
-pub enum Action {
-    ChangeDirection,
+use super::snake::SnakeAction;
+use crate::settings::Settings;
+
+/// Number of gamepads that can be assigned to the players.
+pub const MAX_GAMEPADS: usize = 4;
+
+/// Inputs steering the snake of one player.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerBindings {
+    pub up: KeyCode,
+    pub down: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+    /// Gamepad whose d-pad and left stick also steer the snake, if any.
+    pub gamepad: Option<usize>,
+    /// Deflection of the left stick, between `0.0` and `1.0`, below which it is ignored.
+    pub dead_zone: f32,
+}
+
+impl Default for PlayerBindings {
+    fn default() -> Self {
+        Self::arrows()
+    }
+}
+
+impl PlayerBindings {
+    pub fn arrows() -> Self {
+        Self {
+            up: KeyCode::Up,
+            down: KeyCode::Down,
+            left: KeyCode::Left,
+            right: KeyCode::Right,
+            gamepad: Some(0),
+            dead_zone: 0.3,
+        }
+    }
+
+    pub fn wasd() -> Self {
+        Self {
+            up: KeyCode::W,
+            down: KeyCode::S,
+            left: KeyCode::A,
+            right: KeyCode::D,
+            gamepad: Some(1),
+            dead_zone: 0.3,
+        }
+    }
+
+    pub fn key(&self, action: SnakeAction) -> KeyCode {
+        match action {
+            SnakeAction::MoveUp => self.up,
+            SnakeAction::MoveDown => self.down,
+            SnakeAction::MoveLeft => self.left,
+            SnakeAction::MoveRight => self.right,
+        }
+    }
+
+    pub fn set_key(&mut self, action: SnakeAction, key: KeyCode) {
+        match action {
+            SnakeAction::MoveUp => self.up = key,
+            SnakeAction::MoveDown => self.down = key,
+            SnakeAction::MoveLeft => self.left = key,
+            SnakeAction::MoveRight => self.right = key,
+        }
+    }
+
+    pub fn input_map(&self) -> InputMap<SnakeAction> {
+        let mut input_map = InputMap::default();
+
+        for action in SnakeAction::variants() {
+            input_map.insert(self.key(action), action);
+        }
+
+        if let Some(gamepad) = self.gamepad {
+            let dead_zone = self.dead_zone.clamp(0.0, 1.0);
+
+            input_map
+                .set_gamepad(Gamepad::new(gamepad))
+                .insert(GamepadButtonType::DPadUp, SnakeAction::MoveUp)
+                .insert(GamepadButtonType::DPadDown, SnakeAction::MoveDown)
+                .insert(GamepadButtonType::DPadLeft, SnakeAction::MoveLeft)
+                .insert(GamepadButtonType::DPadRight, SnakeAction::MoveRight)
+                .insert(
+                    SingleAxis::positive_only(GamepadAxisType::LeftStickY, dead_zone),
+                    SnakeAction::MoveUp,
+                )
+                .insert(
+                    SingleAxis::negative_only(GamepadAxisType::LeftStickY, -dead_zone),
+                    SnakeAction::MoveDown,
+                )
+                .insert(
+                    SingleAxis::negative_only(GamepadAxisType::LeftStickX, -dead_zone),
+                    SnakeAction::MoveLeft,
+                )
+                .insert(
+                    SingleAxis::positive_only(GamepadAxisType::LeftStickX, dead_zone),
+                    SnakeAction::MoveRight,
+                );
+        }
+
+        input_map
+    }
+}
+
+/// Bindings of every player, the first one being used by the first snake spawned and so on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ControlSettings {
+    pub players: Vec<PlayerBindings>,
+}
+
+impl Default for ControlSettings {
+    fn default() -> Self {
+        Self {
+            players: vec![PlayerBindings::arrows(), PlayerBindings::wasd()],
+        }
+    }
+}
+
+impl ControlSettings {
+    /// Returns the bindings of the given player, falling back to the default ones.
+    pub fn player(&self, player: usize) -> PlayerBindings {
+        self.players.get(player).cloned().unwrap_or_default()
+    }
+
+    /// Returns the player and the action already bound to the given key, other than the given ones.
+    pub fn key_conflict(&self, player: usize, action: SnakeAction, key: KeyCode) -> Option<(usize, SnakeAction)> {
+        self.players.iter().enumerate().find_map(|(i, bindings)| {
+            SnakeAction::variants()
+                .filter(|other_action| i != player || *other_action != action)
+                .find(|other_action| bindings.key(*other_action) == key)
+                .map(|other_action| (i, other_action))
+        })
+    }
+
+    /// Returns the other player the given gamepad is already assigned to.
+    pub fn gamepad_conflict(&self, player: usize, gamepad: usize) -> Option<usize> {
+        self.players
+            .iter()
+            .enumerate()
+            .position(|(i, bindings)| i != player && bindings.gamepad == Some(gamepad))
+    }
 }
 
-pub struct InputController;
+/// Player steering the snake of this entity, as an index into the [`ControlSettings`].
+#[derive(Debug, Copy, Clone, Component)]
+pub struct Controller(pub usize);
 
-impl Plugin for InputController {
+pub struct InputControllerPlugin;
+
+impl Plugin for InputControllerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(InputManagerPlugin::<Action>::default())
+        app.add_system(apply_bindings);
+    }
+}
+
+/// Keeps the input maps of the controlled snakes in sync with the bindings of their player.
+fn apply_bindings(
+    settings: Res<Settings>,
+    mut query: Query<(&Controller, &mut InputMap<SnakeAction>, ChangeTrackers<Controller>)>,
+) {
+    query.for_each_mut(|(controller, mut input_map, controller_tracker)| {
+        if settings.is_changed() || controller_tracker.is_changed() {
+            *input_map = settings.controls.player(controller.0).input_map();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{ControlSettings, PlayerBindings};
+    use crate::game::snake::SnakeAction;
+
+    #[test]
+    fn it_detects_key_conflicts_between_players() {
+        let controls = ControlSettings::default();
+
+        assert_eq!(
+            controls.key_conflict(0, SnakeAction::MoveUp, KeyCode::D),
+            Some((1, SnakeAction::MoveRight))
+        );
+        assert_eq!(
+            controls.key_conflict(1, SnakeAction::MoveUp, KeyCode::Left),
+            Some((0, SnakeAction::MoveLeft))
+        );
+        assert_eq!(controls.key_conflict(0, SnakeAction::MoveUp, KeyCode::Up), None);
+        assert_eq!(controls.key_conflict(0, SnakeAction::MoveUp, KeyCode::I), None);
+    }
+
+    #[test]
+    fn it_detects_gamepad_conflicts_between_players() {
+        let mut controls = ControlSettings::default();
+
+        assert_eq!(controls.gamepad_conflict(0, 1), Some(1));
+        assert_eq!(controls.gamepad_conflict(0, 0), None);
+
+        controls.players.push(PlayerBindings {
+            gamepad: None,
+            ..PlayerBindings::wasd()
+        });
+
+        assert_eq!(controls.gamepad_conflict(2, 2), None);
     }
 }
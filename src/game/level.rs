@@ -0,0 +1,492 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::num::NonZeroUsize;
+use std::path::Path;
+
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
+
+use super::bush::{BushBuilder, BushParameters};
+use super::components::{Direction, Position};
+use super::tile_map::{TileMap, TILE_SIZE};
+use super::wind::{WindParameters, WindTimer};
+use crate::assets::TextureAssets;
+use crate::settings::{ColorPalette, Settings};
+
+pub const MIN_LEVEL_SIZE: u32 = 3;
+pub const MIN_SNAKE_LENGTH: usize = 2;
+pub const DEFAULT_LEVEL: &str = "maps/meadow_16x9.json";
+
+/// Marks every entity spawned from a [`Level`] description.
+#[derive(Debug, Component)]
+pub struct LevelEntity;
+
+#[derive(Debug, Display)]
+pub enum LevelError {
+    #[display(fmt = "cannot access level file: {_0}")]
+    Io(io::Error),
+    #[display(fmt = "invalid level file: {_0}")]
+    Json(serde_json::Error),
+    #[display(fmt = "level of {_0}x{_1} is smaller than {MIN_LEVEL_SIZE}x{MIN_LEVEL_SIZE}")]
+    TooSmall(u32, u32),
+    #[display(fmt = "tile at ({_0}, {_1}) is out of the level")]
+    OutOfBounds(u32, u32),
+    #[display(fmt = "snake of {_0} fragments is shorter than {MIN_SNAKE_LENGTH}")]
+    SnakeTooShort(usize),
+    #[display(fmt = "snake of {_0} fragments does not fit in the {_1} tiles available")]
+    SnakeTooLong(usize, usize),
+}
+
+impl std::error::Error for LevelError {}
+
+impl From<io::Error> for LevelError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for LevelError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TileEntity {
+    Bush,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Component)]
+#[serde(rename_all = "kebab-case")]
+pub enum Terrain {
+    #[default]
+    Grass,
+    Dirt,
+    Sand,
+}
+
+impl Terrain {
+    pub const fn color(self, palette: ColorPalette) -> Color {
+        match (palette, self) {
+            (ColorPalette::Default, Self::Grass) => Color::rgb(0.5, 0.9, 0.19),
+            (ColorPalette::Default, Self::Dirt) => Color::rgb(0.55, 0.4, 0.25),
+            (ColorPalette::Default, Self::Sand) => Color::rgb(0.93, 0.84, 0.55),
+            // red and green look alike, so rely on blue and on brightness instead
+            (ColorPalette::Deuteranopia | ColorPalette::Protanopia, Self::Grass) => Color::rgb(0.35, 0.6, 0.9),
+            (ColorPalette::Deuteranopia | ColorPalette::Protanopia, Self::Dirt) => Color::rgb(0.3, 0.27, 0.25),
+            (ColorPalette::Deuteranopia | ColorPalette::Protanopia, Self::Sand) => Color::rgb(0.97, 0.88, 0.45),
+            // blue and yellow look alike, so rely on red and green instead
+            (ColorPalette::Tritanopia, Self::Grass) => Color::rgb(0.2, 0.7, 0.55),
+            (ColorPalette::Tritanopia, Self::Dirt) => Color::rgb(0.4, 0.25, 0.25),
+            (ColorPalette::Tritanopia, Self::Sand) => Color::rgb(0.95, 0.65, 0.7),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelTile {
+    #[serde(rename = "type")]
+    pub entity: TileEntity,
+    pub x: u32,
+    pub y: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub angle: Option<f32>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TerrainTile {
+    #[serde(rename = "type")]
+    pub terrain: Terrain,
+    pub x: u32,
+    pub y: u32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnakeSpawn {
+    pub x: u32,
+    pub y: u32,
+    pub direction: Direction,
+    pub length: NonZeroUsize,
+}
+
+impl SnakeSpawn {
+    pub const fn position(&self) -> IVec3 {
+        IVec3::new(self.x as i32, self.y as i32, 1)
+    }
+
+    /// Returns the length of the longest snake fitting in a level of the given size, its body lying along its
+    /// direction.
+    pub const fn max_length(&self, width: u32, height: u32) -> usize {
+        match self.direction {
+            Direction::Up | Direction::Down => height as usize,
+            Direction::Left | Direction::Right => width as usize,
+        }
+    }
+}
+
+/// Description of a playable map, as stored in `assets/maps`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypeUuid)]
+#[uuid = "0f8e8b4a-95c7-458f-a9ba-b25229c5f2d8"]
+pub struct Level {
+    pub width: u32,
+    pub height: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tiles: Vec<LevelTile>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub terrain: Vec<TerrainTile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snake: Option<SnakeSpawn>,
+    /// Wind blowing over the level, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wind: Option<WindParameters>,
+    /// Regrowth of the destroyed bushes, which never grow back without it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bushes: Option<BushParameters>,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Self {
+            width: 16,
+            height: 9,
+            tiles: [(5, 5, 50.0), (12, 8, 0.0), (1, 0, 170.0), (2, 4, 234.0)]
+                .into_iter()
+                .map(|(x, y, angle)| LevelTile {
+                    entity: TileEntity::Bush,
+                    x,
+                    y,
+                    angle: Some(angle),
+                })
+                .collect(),
+            terrain: Vec::new(),
+            snake: Some(SnakeSpawn {
+                x: 4,
+                y: 5,
+                direction: Direction::Down,
+                length: NonZeroUsize::new(4).unwrap(),
+            }),
+            wind: Some(WindParameters::default()),
+            bushes: Some(BushParameters::default()),
+        }
+    }
+}
+
+impl Level {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LevelError> {
+        let file = File::open(path)?;
+        let level = serde_json::from_reader::<_, Self>(BufReader::new(file))?;
+
+        level.validate()?;
+
+        Ok(level)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), LevelError> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let file = File::create(path)?;
+
+        Ok(serde_json::to_writer_pretty(BufWriter::new(file), self)?)
+    }
+
+    /// Checks that the level can be played: the map is large enough, the tiles lie within it and the snake fits in
+    /// it.
+    pub fn validate(&self) -> Result<(), LevelError> {
+        if self.width < MIN_LEVEL_SIZE || self.height < MIN_LEVEL_SIZE {
+            return Err(LevelError::TooSmall(self.width, self.height));
+        }
+
+        let positions = self.tiles.iter().map(|tile| (tile.x, tile.y));
+        let terrain_positions = self.terrain.iter().map(|tile| (tile.x, tile.y));
+        let snake_position = self.snake.iter().map(|snake| (snake.x, snake.y));
+
+        if let Some((x, y)) = positions
+            .chain(terrain_positions)
+            .chain(snake_position)
+            .find(|(x, y)| *x >= self.width || *y >= self.height)
+        {
+            return Err(LevelError::OutOfBounds(x, y));
+        }
+
+        if let Some(snake) = &self.snake {
+            let length = snake.length.get();
+            let max_length = snake.max_length(self.width, self.height);
+
+            if length < MIN_SNAKE_LENGTH {
+                return Err(LevelError::SnakeTooShort(length));
+            }
+
+            if length > max_length {
+                return Err(LevelError::SnakeTooLong(length, max_length));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the snake spawn, falling back to the center of the map heading down.
+    pub fn snake_spawn(&self) -> SnakeSpawn {
+        self.snake.unwrap_or(SnakeSpawn {
+            x: self.width / 2,
+            y: self.height / 2,
+            direction: Direction::Down,
+            length: NonZeroUsize::new(4.min(self.height as usize).max(MIN_SNAKE_LENGTH)).unwrap(),
+        })
+    }
+
+    pub fn tile_at(&self, x: u32, y: u32) -> Option<&LevelTile> {
+        self.tiles.iter().find(|tile| tile.x == x && tile.y == y)
+    }
+
+    pub fn set_tile_at(&mut self, x: u32, y: u32, entity: Option<TileEntity>) {
+        self.tiles.retain(|tile| tile.x != x || tile.y != y);
+
+        if let Some(entity) = entity {
+            self.tiles.push(LevelTile {
+                entity,
+                x,
+                y,
+                angle: None,
+            });
+        }
+    }
+
+    pub fn terrain_at(&self, x: u32, y: u32) -> Terrain {
+        self.terrain
+            .iter()
+            .find(|tile| tile.x == x && tile.y == y)
+            .map_or_else(Terrain::default, |tile| tile.terrain)
+    }
+
+    pub fn set_terrain_at(&mut self, x: u32, y: u32, terrain: Terrain) {
+        self.terrain.retain(|tile| tile.x != x || tile.y != y);
+
+        if terrain != Terrain::default() {
+            self.terrain.push(TerrainTile { terrain, x, y });
+        }
+    }
+
+    /// Resizes the level, dropping everything that no longer fits in it.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width.max(MIN_LEVEL_SIZE);
+        self.height = height.max(MIN_LEVEL_SIZE);
+
+        let (width, height) = (self.width, self.height);
+
+        self.tiles.retain(|tile| tile.x < width && tile.y < height);
+        self.terrain.retain(|tile| tile.x < width && tile.y < height);
+
+        if let Some(snake) = &mut self.snake {
+            snake.x = snake.x.min(width - 1);
+            snake.y = snake.y.min(height - 1);
+            snake.length = snake.length.min(NonZeroUsize::new(snake.max_length(width, height)).unwrap());
+        }
+    }
+}
+
+/// Level selected to be played.
+pub struct CurrentLevel(pub Handle<Level>);
+
+/// Copy of the level currently spawned, whatever it comes from.
+#[derive(Debug, Clone)]
+pub struct ActiveLevel(pub Level);
+
+impl FromWorld for CurrentLevel {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.get_resource::<AssetServer>().unwrap();
+
+        Self(asset_server.load(DEFAULT_LEVEL))
+    }
+}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let level = serde_json::from_slice::<Level>(bytes)?;
+
+            level.validate()?;
+
+            load_context.set_default_asset(LoadedAsset::new(level));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["json"]
+    }
+}
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Level>()
+            .init_asset_loader::<LevelLoader>()
+            .add_system(apply_palette);
+    }
+}
+
+fn apply_palette(settings: Res<Settings>, mut query: Query<(&mut Sprite, &Terrain, ChangeTrackers<Terrain>)>) {
+    query.for_each_mut(|(mut sprite, terrain, terrain_tracker)| {
+        if settings.is_changed() || terrain_tracker.is_changed() {
+            sprite.color = terrain.color(settings.palette);
+        }
+    });
+}
+
+/// Spawns the tiles and obstacles of the level and returns the matching [`TileMap`], which must be inserted as a
+/// resource by the caller. The snake is left to the caller as well.
+pub fn spawn_level(commands: &mut Commands, textures: &TextureAssets, level: &Level) -> TileMap {
+    let tile_map = TileMap::empty(level.width, level.height);
+
+    commands
+        .spawn()
+        .insert_bundle(TransformBundle::default())
+        .insert_bundle(VisibilityBundle::default())
+        .insert_bundle((LevelEntity, Name::new("Tile Map")))
+        .with_children(|parent| {
+            tile_map.tiles().for_each(|tile| {
+                parent
+                    .spawn_bundle(SpriteBundle {
+                        sprite: Sprite {
+                            custom_size: Some(Vec2::splat(TILE_SIZE)),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .insert(level.terrain_at(tile.x(), tile.y()))
+                    .insert(Position(IVec3::new(tile.x() as i32, tile.y() as i32, 0)))
+                    .insert(Name::new(format!("Tile ({}, {})", tile.x(), tile.y())));
+            });
+        });
+
+    for tile in &level.tiles {
+        match tile.entity {
+            TileEntity::Bush => {
+                // keep the angle stable between two spawns of the same level
+                let angle = tile.angle.unwrap_or(((tile.x * 73 + tile.y * 151) % 360) as f32);
+
+                let entity = BushBuilder::default()
+                    .with_position(IVec3::new(tile.x as i32, tile.y as i32, 1))
+                    .with_angle(angle)
+                    .spawn(commands, textures);
+
+                commands.entity(entity).insert(LevelEntity);
+            }
+        }
+    }
+
+    tile_map
+}
+
+/// Inserts the resources driving the wind and the bushes of the level, or removes them if the level has none.
+pub fn insert_parameters(commands: &mut Commands, level: &Level) {
+    if let Some(wind) = &level.wind {
+        commands.insert_resource(WindTimer::new(wind.interval.clone()));
+        commands.insert_resource(wind.clone());
+    } else {
+        commands.remove_resource::<WindTimer>();
+        commands.remove_resource::<WindParameters>();
+    }
+
+    match &level.bushes {
+        Some(bushes) => commands.insert_resource(bushes.clone()),
+        None => commands.remove_resource::<BushParameters>(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use super::{Level, LevelError, TileEntity};
+    use crate::game::components::Direction;
+
+    #[test]
+    fn it_parses_bundled_maps() {
+        for map in ["empty_10x10.json", "closed_10x10.json", "meadow_16x9.json", "tutorial.json"] {
+            let path = format!("{}/assets/maps/{map}", env!("CARGO_MANIFEST_DIR"));
+
+            assert!(Level::load(&path).is_ok(), "{map} is a valid level");
+        }
+    }
+
+    #[test]
+    fn it_drops_tiles_outside_of_resized_level() {
+        let mut level = Level::default();
+
+        level.set_tile_at(15, 8, Some(TileEntity::Bush));
+        level.resize(10, 5);
+
+        assert!(level.tile_at(15, 8).is_none());
+        assert!(level.tiles.iter().all(|tile| tile.x < 10 && tile.y < 5));
+        assert!(level.snake_spawn().x < 10 && level.snake_spawn().y < 5);
+    }
+
+    #[test]
+    fn it_rejects_levels_smaller_than_the_minimum() {
+        let level = Level {
+            width: 0,
+            ..Level::default()
+        };
+
+        let error = level.validate().unwrap_err();
+
+        assert!(matches!(error, LevelError::TooSmall(0, 9)));
+        assert_eq!(error.to_string(), "level of 0x9 is smaller than 3x3");
+    }
+
+    #[test]
+    fn it_rejects_tiles_out_of_the_level() {
+        let mut level = Level::default();
+        level.tiles[0].x = 16;
+
+        assert!(matches!(level.validate(), Err(LevelError::OutOfBounds(16, _))));
+    }
+
+    #[test]
+    fn it_rejects_snakes_too_short() {
+        let mut level = Level::default();
+        level.snake.as_mut().unwrap().length = NonZeroUsize::new(1).unwrap();
+
+        assert!(matches!(level.validate(), Err(LevelError::SnakeTooShort(1))));
+    }
+
+    #[test]
+    fn it_rejects_snakes_longer_than_the_level() {
+        let mut level = Level::default();
+        let snake = level.snake.as_mut().unwrap();
+        snake.direction = Direction::Up;
+        snake.length = NonZeroUsize::new(10).unwrap();
+
+        assert!(matches!(level.validate(), Err(LevelError::SnakeTooLong(10, 9))));
+
+        level.snake.as_mut().unwrap().direction = Direction::Left;
+        assert!(level.validate().is_ok());
+    }
+
+    #[test]
+    fn it_shortens_the_snake_of_shrunk_levels() {
+        let mut level = Level::default();
+
+        level.resize(3, 3);
+
+        assert_eq!(level.snake_spawn().length.get(), 3);
+        assert!(level.validate().is_ok());
+    }
+}
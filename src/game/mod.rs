@@ -1,5 +1,19 @@
+pub mod animation;
 pub mod apple;
+pub mod campaign;
+pub mod camera;
 pub mod components;
+pub mod daily;
+pub mod expression;
+pub mod input_controller;
+pub mod level;
+pub mod particles;
+pub mod play_mode;
+pub mod power_up;
+pub mod rewind;
+pub mod rng;
+pub mod save;
+pub mod score;
 // pub mod collision;
 pub mod snake;
 pub mod tile_map;
@@ -10,7 +24,21 @@ pub mod wind;
 
 use bevy::prelude::*;
 
+use animation::SnakeAnimationPlugin;
 use apple::ApplePlugin;
+use bush::BushPlugin;
+use campaign::CampaignPlugin;
+use camera::CameraPlugin;
+use daily::DailyPlugin;
+use expression::ExpressionPlugin;
+use input_controller::InputControllerPlugin;
+use level::LevelPlugin;
+use particles::ParticlePlugin;
+use play_mode::PlayModePlugin;
+use power_up::PowerUpPlugin;
+use rewind::RewindPlugin;
+use rng::GameRng;
+use save::SavePlugin;
 use snake::SnakePlugin;
 use tile_map::TileMapPlugin;
 use value_tracker::ValueTrackerPlugin;
@@ -26,9 +54,12 @@ pub enum GameStage {
 
 #[derive(SystemLabel)]
 pub enum GameSystem {
+    SpawnApple,
     Movement,
     CheckPosition,
     CollisionDetection,
+    Death,
+    RecordScore,
 }
 
 pub struct GamePlugin;
@@ -38,10 +69,24 @@ impl Plugin for GamePlugin {
         app.add_stage_after(CoreStage::Update, GameStage::Track, SystemStage::parallel())
             .add_stage_after(GameStage::Track, GameStage::Transform, SystemStage::parallel())
             .add_stage_after(GameStage::Transform, GameStage::Cleanup, SystemStage::parallel())
+            .init_resource::<GameRng>()
             .add_plugin(ValueTrackerPlugin::<Direction>::default())
+            .add_plugin(LevelPlugin)
             .add_plugin(ApplePlugin)
+            .add_plugin(BushPlugin)
             .add_plugin(SnakePlugin)
+            .add_plugin(ExpressionPlugin)
+            .add_plugin(SnakeAnimationPlugin)
+            .add_plugin(InputControllerPlugin)
             .add_plugin(WindPlugin)
+            .add_plugin(CameraPlugin)
+            .add_plugin(ParticlePlugin)
+            .add_plugin(RewindPlugin)
+            .add_plugin(SavePlugin)
+            .add_plugin(CampaignPlugin)
+            .add_plugin(DailyPlugin)
+            .add_plugin(PlayModePlugin)
+            .add_plugin(PowerUpPlugin)
             .add_plugin(TileMapPlugin);
         // .add_plugin(CollisionPlugin);
     }
@@ -0,0 +1,284 @@
+use std::f32::consts::PI;
+use std::ops::Range;
+
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+use rand::Rng;
+
+use super::components::Direction;
+use super::tile_map::TileMapOptions;
+
+/// Depth at which particles are drawn, above everything else on the tile map.
+const PARTICLE_DEPTH: f32 = 10.0;
+
+/// Describes how particles look and move. Sizes, distances and speeds are expressed in tiles so that particles follow
+/// the scale of the tile map.
+#[derive(Debug, Clone)]
+pub struct ParticleConfig {
+    pub texture: Option<Handle<Image>>,
+    pub lifetime: Range<f32>,
+    pub speed: Range<f32>,
+    /// Direction in which particles are emitted, in radians.
+    pub angle: f32,
+    /// Maximum deviation from the emission angle, in radians.
+    pub spread: f32,
+    /// Half extents of the area around the emitter where particles appear.
+    pub area: Vec2,
+    pub start_size: Vec2,
+    pub end_size: Vec2,
+    pub start_color: Color,
+    pub end_color: Color,
+    /// Rotates particles along their velocity, which is useful for streaks.
+    pub is_aligned: bool,
+}
+
+impl Default for ParticleConfig {
+    fn default() -> Self {
+        Self {
+            texture: None,
+            lifetime: 0.5..1.0,
+            speed: 1.0..2.0,
+            angle: 0.0,
+            spread: PI,
+            area: Vec2::ZERO,
+            start_size: Vec2::splat(0.1),
+            end_size: Vec2::ZERO,
+            start_color: Color::WHITE,
+            end_color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+            is_aligned: false,
+        }
+    }
+}
+
+impl ParticleConfig {
+    pub fn wind_streaks(direction: Direction, width: u32) -> Self {
+        Self {
+            lifetime: 0.3..0.6,
+            speed: 6.0..10.0,
+            angle: direction.to_vec2().y.atan2(direction.to_vec2().x),
+            spread: 0.05,
+            area: wind_area(direction, width),
+            start_size: Vec2::new(0.5, 0.03),
+            end_size: Vec2::new(0.8, 0.01),
+            start_color: Color::rgba(1.0, 1.0, 1.0, 0.6),
+            end_color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+            is_aligned: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn wind_leaves(direction: Direction, width: u32, texture: Handle<Image>) -> Self {
+        Self {
+            texture: Some(texture),
+            lifetime: 0.6..1.2,
+            speed: 3.0..5.0,
+            angle: direction.to_vec2().y.atan2(direction.to_vec2().x),
+            spread: 0.4,
+            area: wind_area(direction, width),
+            start_size: Vec2::splat(0.3),
+            end_size: Vec2::splat(0.2),
+            start_color: Color::WHITE,
+            end_color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+            ..Default::default()
+        }
+    }
+
+    pub fn apple_burst() -> Self {
+        Self {
+            lifetime: 0.3..0.7,
+            speed: 1.5..3.0,
+            area: Vec2::splat(0.1),
+            start_size: Vec2::splat(0.12),
+            end_size: Vec2::splat(0.02),
+            start_color: Color::rgb(0.85, 0.1, 0.1),
+            end_color: Color::rgba(0.95, 0.6, 0.2, 0.0),
+            ..Default::default()
+        }
+    }
+
+    pub fn bush_shatter(texture: Handle<Image>) -> Self {
+        Self {
+            texture: Some(texture),
+            lifetime: 0.4..0.9,
+            speed: 1.0..2.5,
+            area: Vec2::splat(0.3),
+            start_size: Vec2::splat(0.3),
+            end_size: Vec2::splat(0.1),
+            start_color: Color::rgb(0.3, 0.6, 0.15),
+            end_color: Color::rgba(0.4, 0.35, 0.1, 0.0),
+            ..Default::default()
+        }
+    }
+}
+
+/// Area covered by a gust of the given direction and width.
+fn wind_area(direction: Direction, width: u32) -> Vec2 {
+    match direction {
+        Direction::Up | Direction::Down => Vec2::new(width as f32 / 2.0, 0.5),
+        Direction::Left | Direction::Right => Vec2::new(0.5, width as f32 / 2.0),
+    }
+}
+
+/// Continuously emits particles around its entity.
+#[derive(Debug, Component)]
+pub struct ParticleEmitter {
+    pub config: ParticleConfig,
+    /// Number of particles emitted every second.
+    pub rate: f32,
+    pending: f32,
+}
+
+impl ParticleEmitter {
+    pub fn new(config: ParticleConfig, rate: f32) -> Self {
+        Self {
+            config,
+            rate,
+            pending: 0.0,
+        }
+    }
+}
+
+/// Requests a one-shot emission of particles at the given world position.
+#[derive(Debug, Clone)]
+pub struct ParticleBurst {
+    pub config: ParticleConfig,
+    pub translation: Vec2,
+    pub count: usize,
+}
+
+#[derive(Debug, Component)]
+struct Particle {
+    velocity: Vec2,
+    lifetime: Timer,
+    start_size: Vec2,
+    end_size: Vec2,
+    start_color: Color,
+    end_color: Color,
+}
+
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ParticleBurst>().add_system_set(
+            ConditionSet::new()
+                .run_if_resource_exists::<TileMapOptions>()
+                .with_system(emit_particles)
+                .with_system(burst_particles)
+                .with_system(update_particles)
+                .into(),
+        );
+    }
+}
+
+fn emit_particles(
+    mut commands: Commands,
+    mut emitter_query: Query<(&mut ParticleEmitter, &GlobalTransform)>,
+    tile_map_options: Res<TileMapOptions>,
+    time: Res<Time>,
+) {
+    let mut rng = rand::thread_rng();
+
+    emitter_query.for_each_mut(|(mut emitter, transform)| {
+        emitter.pending += emitter.rate * time.delta_seconds();
+
+        while emitter.pending >= 1.0 {
+            emitter.pending -= 1.0;
+
+            spawn_particle(
+                &mut commands,
+                &mut rng,
+                &emitter.config,
+                transform.translation().xy(),
+                tile_map_options.tile_size,
+            );
+        }
+    });
+}
+
+fn burst_particles(
+    mut commands: Commands,
+    mut burst_reader: EventReader<ParticleBurst>,
+    tile_map_options: Res<TileMapOptions>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for burst in burst_reader.iter() {
+        for _ in 0..burst.count {
+            spawn_particle(
+                &mut commands,
+                &mut rng,
+                &burst.config,
+                burst.translation,
+                tile_map_options.tile_size,
+            );
+        }
+    }
+}
+
+fn spawn_particle(commands: &mut Commands, rng: &mut impl Rng, config: &ParticleConfig, origin: Vec2, tile_size: f32) {
+    let angle = config.angle + rng.gen_range(-config.spread..=config.spread);
+    let velocity = Vec2::new(angle.cos(), angle.sin()) * rng.gen_range(config.speed.clone());
+    let offset = Vec2::new(
+        rng.gen_range(-config.area.x..=config.area.x),
+        rng.gen_range(-config.area.y..=config.area.y),
+    );
+
+    let rotation = if config.is_aligned {
+        Quat::from_rotation_z(angle)
+    } else {
+        Quat::from_rotation_z(rng.gen_range(0.0..2.0 * PI))
+    };
+
+    let mut sprite_bundle = SpriteBundle {
+        sprite: Sprite {
+            color: config.start_color,
+            custom_size: Some(Vec2::splat(tile_size)),
+            ..default()
+        },
+        transform: Transform::from_translation((origin + offset * tile_size).extend(PARTICLE_DEPTH))
+            .with_rotation(rotation)
+            .with_scale(config.start_size.extend(1.0)),
+        ..default()
+    };
+
+    if let Some(texture) = &config.texture {
+        sprite_bundle.texture = texture.clone();
+    }
+
+    commands
+        .spawn_bundle(sprite_bundle)
+        .insert(Particle {
+            velocity,
+            lifetime: Timer::from_seconds(rng.gen_range(config.lifetime.clone()), false),
+            start_size: config.start_size,
+            end_size: config.end_size,
+            start_color: config.start_color,
+            end_color: config.end_color,
+        })
+        .insert(Name::new("Particle"));
+}
+
+fn update_particles(
+    mut commands: Commands,
+    mut particle_query: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+    tile_map_options: Res<TileMapOptions>,
+    time: Res<Time>,
+) {
+    particle_query.for_each_mut(|(entity, mut particle, mut transform, mut sprite)| {
+        if particle.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+
+            return;
+        }
+
+        let t = particle.lifetime.percent();
+
+        transform.translation += (particle.velocity * tile_map_options.tile_size * time.delta_seconds()).extend(0.0);
+        transform.scale = particle.start_size.lerp(particle.end_size, t).extend(1.0);
+        sprite.color = Vec4::from(particle.start_color)
+            .lerp(Vec4::from(particle.end_color), t)
+            .into();
+    });
+}
@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::apple::{AppleExpired, AppleSpawner};
+use super::components::Lifetime;
+use super::level::MIN_SNAKE_LENGTH;
+use super::save::PendingLoad;
+use super::score::Score;
+use super::snake::{Dead, Immortal, MoveTimer, Snake, SnakeFragment};
+use super::GameSystem;
+use crate::states::{AppScreen, GameStatus};
+use crate::storage;
+
+const HIGH_SCORES_FILE: &str = "high_scores.json";
+/// Number of scores kept in the table of each mode.
+pub const HIGH_SCORE_COUNT: usize = 5;
+
+/// Rules of the game, deciding how apples spawn, how fast the snake goes and how the game ends.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PlayMode {
+    /// The game goes on until the snake dies.
+    #[default]
+    Endless,
+    /// The game ends after a minute, the snake going faster.
+    #[serde(rename = "time-attack-60")]
+    TimeAttack60,
+    /// The game ends after two minutes, the snake going faster.
+    #[serde(rename = "time-attack-120")]
+    TimeAttack120,
+    /// Apples rot quickly, and every rotten apple shortens the snake until it dies of hunger.
+    Survival,
+    /// The snake never dies, going through whatever it runs into.
+    Zen,
+}
+
+impl PlayMode {
+    pub const ALL: [Self; 5] = [
+        Self::Endless,
+        Self::TimeAttack60,
+        Self::TimeAttack120,
+        Self::Survival,
+        Self::Zen,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Endless => "Endless",
+            Self::TimeAttack60 => "Time attack 60s",
+            Self::TimeAttack120 => "Time attack 120s",
+            Self::Survival => "Survival",
+            Self::Zen => "Zen",
+        }
+    }
+
+    /// Duration of the game, in seconds, if it is limited.
+    pub const fn time_limit(self) -> Option<f32> {
+        match self {
+            Self::TimeAttack60 => Some(60.0),
+            Self::TimeAttack120 => Some(120.0),
+            Self::Endless | Self::Survival | Self::Zen => None,
+        }
+    }
+
+    /// Factor applied to the delay between two moves of the snake chosen in the settings.
+    pub const fn move_interval_factor(self) -> f32 {
+        match self {
+            Self::TimeAttack60 | Self::TimeAttack120 => 0.85,
+            Self::Zen => 1.2,
+            Self::Endless | Self::Survival => 1.0,
+        }
+    }
+
+    pub const fn is_immortal(self) -> bool {
+        matches!(self, Self::Zen)
+    }
+
+    /// Adapts the apples of a regular game to the mode.
+    pub fn configure(self, apple_spawner: &mut AppleSpawner) {
+        match self {
+            Self::Endless => (),
+            Self::TimeAttack60 | Self::TimeAttack120 => {
+                apple_spawner.timer = Timer::from_seconds(1.0, true);
+                apple_spawner.max_apples = 4;
+            }
+            Self::Survival => {
+                apple_spawner.max_apples = 2;
+                apple_spawner.apple_lifetime = Some(Lifetime::from_seconds(2.5));
+                apple_spawner.golden_apple_chance = 0.0;
+            }
+            Self::Zen => apple_spawner.apple_lifetime = None,
+        }
+    }
+}
+
+/// Time left before a time attack is over.
+#[derive(Debug, Deref, DerefMut)]
+pub struct ModeClock(pub Timer);
+
+/// Best scores of each mode, persisted between two runs of the game.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HighScores {
+    tables: HashMap<PlayMode, Vec<u32>>,
+    /// Mode and rank of the score recorded at the end of the last game, if it made it to the table.
+    #[serde(skip)]
+    pub last_entry: Option<(PlayMode, usize)>,
+}
+
+impl HighScores {
+    /// Loads the high scores, starting over if they are missing or invalid.
+    pub fn load() -> Self {
+        storage::load(HIGH_SCORES_FILE)
+            .unwrap_or_else(|e| {
+                warn!("ignoring high scores: {e}");
+
+                None
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = storage::save(HIGH_SCORES_FILE, self) {
+            error!("cannot save high scores: {e}");
+        }
+    }
+
+    /// Returns the best scores of the mode, from the highest to the lowest.
+    pub fn table(&self, mode: PlayMode) -> &[u32] {
+        self.tables.get(&mode).map_or(&[], Vec::as_slice)
+    }
+
+    /// Inserts the score in the table of the mode, returning its rank unless it is too low to be kept.
+    pub fn record(&mut self, mode: PlayMode, score: u32) -> Option<usize> {
+        let table = self.tables.entry(mode).or_default();
+        let rank = table.iter().position(|best| score > *best).unwrap_or(table.len());
+
+        if rank >= HIGH_SCORE_COUNT || score == 0 {
+            return None;
+        }
+
+        table.insert(rank, score);
+        table.truncate(HIGH_SCORE_COUNT);
+
+        Some(rank)
+    }
+}
+
+/// Marks a game whose score goes to the high scores of its mode, which a tutorial or a campaign level does not.
+#[derive(Debug, Default)]
+pub struct Ranked;
+
+pub struct PlayModePlugin;
+
+impl Plugin for PlayModePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayMode>()
+            .insert_resource(HighScores::load())
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppScreen::InGame)
+                    .with_system(configure_game)
+                    .with_system(make_immortal)
+                    .with_system(
+                        count_down
+                            .run_in_state(GameStatus::Running)
+                            .run_if_resource_exists::<ModeClock>(),
+                    )
+                    .with_system(leave_zen.run_in_state(GameStatus::Running))
+                    .into(),
+            )
+            .add_system(
+                starve_snakes
+                    .run_in_state(AppScreen::InGame)
+                    .after(GameSystem::CollisionDetection)
+                    .before(GameSystem::Death),
+            )
+            .add_enter_system(
+                GameStatus::GameOver,
+                record_high_score
+                    .run_if_resource_exists::<Ranked>()
+                    .label(GameSystem::RecordScore),
+            );
+    }
+}
+
+/// Applies the mode to the apple spawner created along with the game, and starts the clock of a time attack.
+fn configure_game(
+    mut commands: Commands,
+    mode: Res<PlayMode>,
+    apple_spawner: Option<ResMut<AppleSpawner>>,
+    pending_load: Option<Res<PendingLoad>>,
+) {
+    let mut apple_spawner = match apple_spawner {
+        Some(apple_spawner) if apple_spawner.is_added() => apple_spawner,
+        _ => return,
+    };
+
+    // a saved game brings its own mode, apples and clock along
+    if pending_load.is_some() {
+        return;
+    }
+
+    mode.configure(&mut apple_spawner);
+
+    match mode.time_limit() {
+        Some(seconds) => commands.insert_resource(ModeClock(Timer::from_seconds(seconds, false))),
+        None => commands.remove_resource::<ModeClock>(),
+    }
+}
+
+fn make_immortal(mut commands: Commands, mode: Res<PlayMode>, query: Query<Entity, Added<Snake>>) {
+    if !mode.is_immortal() {
+        return;
+    }
+
+    query.for_each(|entity| {
+        commands.entity(entity).insert(Immortal);
+    });
+}
+
+/// Ends the time attack once the time is up, the snakes standing still under the game over overlay.
+fn count_down(
+    mut commands: Commands,
+    mut clock: ResMut<ModeClock>,
+    time: Res<Time>,
+    snake_query: Query<(), (With<Snake>, Without<Dead>)>,
+    timer_query: Query<&mut MoveTimer>,
+) {
+    if clock.finished() || snake_query.is_empty() {
+        return;
+    }
+
+    if clock.tick(time.delta()).just_finished() {
+        stop_game(&mut commands, timer_query);
+    }
+}
+
+/// Ends a zen game on demand, since the snake never dies.
+fn leave_zen(mut commands: Commands, mode: Res<PlayMode>, keys: Res<Input<KeyCode>>, timer_query: Query<&mut MoveTimer>) {
+    if mode.is_immortal() && keys.just_pressed(KeyCode::Escape) {
+        stop_game(&mut commands, timer_query);
+    }
+}
+
+/// Shows the game over overlay while the snakes are still alive, making them stand still under it.
+fn stop_game(commands: &mut Commands, mut timer_query: Query<&mut MoveTimer>) {
+    timer_query.for_each_mut(|mut timer| timer.pause());
+
+    commands.insert_resource(NextState(GameStatus::GameOver));
+}
+
+/// Shortens the snakes by a fragment for every apple left to rot in survival, killing the ones too short to shrink.
+fn starve_snakes(
+    mut commands: Commands,
+    mode: Res<PlayMode>,
+    mut expired_reader: EventReader<AppleExpired>,
+    mut snake_query: Query<(Entity, &mut Snake), Without<Dead>>,
+    mut fragment_query: Query<&mut SnakeFragment>,
+) {
+    let expired_count = expired_reader.iter().count();
+
+    if *mode != PlayMode::Survival || expired_count == 0 {
+        return;
+    }
+
+    snake_query.for_each_mut(|(entity, mut snake)| {
+        for _ in 0..expired_count {
+            if snake.fragments.len() <= MIN_SNAKE_LENGTH {
+                commands.entity(entity).insert(Dead);
+
+                return;
+            }
+
+            if let Some(tail) = snake.fragments.pop() {
+                commands.entity(tail).despawn_recursive();
+            }
+
+            if let Some(mut fragment) = snake
+                .fragments
+                .last()
+                .and_then(|fragment| fragment_query.get_mut(*fragment).ok())
+            {
+                *fragment = SnakeFragment::Tail;
+            }
+        }
+    });
+}
+
+fn record_high_score(mode: Res<PlayMode>, score: Option<Res<Score>>, mut high_scores: ResMut<HighScores>) {
+    let rank = high_scores.record(*mode, score.map_or(0, |score| score.0));
+
+    high_scores.last_entry = rank.map(|rank| (*mode, rank));
+
+    if rank.is_some() {
+        high_scores.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HighScores, PlayMode, HIGH_SCORE_COUNT};
+
+    #[test]
+    fn it_keeps_separate_tables_per_mode() {
+        let mut high_scores = HighScores::default();
+
+        assert_eq!(high_scores.record(PlayMode::Survival, 12), Some(0));
+        assert_eq!(high_scores.record(PlayMode::Survival, 20), Some(0));
+        assert_eq!(high_scores.record(PlayMode::Survival, 15), Some(1));
+        assert_eq!(high_scores.record(PlayMode::Zen, 3), Some(0));
+
+        assert_eq!(high_scores.table(PlayMode::Survival), [20, 15, 12]);
+        assert_eq!(high_scores.table(PlayMode::Zen), [3]);
+        assert!(high_scores.table(PlayMode::Endless).is_empty());
+    }
+
+    #[test]
+    fn it_drops_scores_too_low_for_the_table() {
+        let mut high_scores = HighScores::default();
+
+        for score in 1..=HIGH_SCORE_COUNT as u32 {
+            high_scores.record(PlayMode::Endless, score * 10);
+        }
+
+        assert_eq!(high_scores.record(PlayMode::Endless, 5), None);
+        assert_eq!(high_scores.record(PlayMode::Endless, 0), None);
+        assert_eq!(high_scores.record(PlayMode::Endless, 25), Some(3));
+        assert_eq!(high_scores.table(PlayMode::Endless).len(), HIGH_SCORE_COUNT);
+    }
+
+    #[test]
+    fn it_persists_tables_by_mode_name() {
+        let mut high_scores = HighScores::default();
+        high_scores.record(PlayMode::TimeAttack60, 7);
+
+        let json = serde_json::to_value(&high_scores).unwrap();
+
+        assert_eq!(json["tables"]["time-attack-60"][0], 7);
+        assert_eq!(serde_json::from_value::<HighScores>(json).unwrap(), high_scores);
+    }
+}
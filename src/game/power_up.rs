@@ -0,0 +1,617 @@
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::time::Duration;
+
+use bevy::ecs::system::SystemParam;
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use bevy_tweening::lens::TransformScaleLens;
+use bevy_tweening::{Animator, EaseFunction, Tween, TweeningType};
+use iyes_loopless::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::apple::Apple;
+use super::components::{Lifetime, Position, RigidBody};
+use super::rng::GameRng;
+use super::snake::{Dead, MoveTimer, Snake};
+use super::tile_map::{Tile, TileMap};
+use super::{GameStage, GameSystem};
+use crate::audio::SoundEffect;
+use crate::states::GameStatus;
+use crate::systems;
+
+/// Factor applied to the delay between two moves of a snake in slow motion.
+pub const SLOW_MOTION_FACTOR: f32 = 1.6;
+/// Distance, in tiles, from which a magnet pulls the apples.
+const MAGNET_RANGE: i32 = 4;
+
+/// How picking a power-up whose effect is still active adds to it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Stacking {
+    /// Adds up the durations, up to the given number of seconds.
+    Duration(f32),
+    /// Raises the level of the effect, up to the given one, starting its duration over.
+    Level(u8),
+}
+
+/// Collectible granting a timed effect to the snake picking it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PowerUp {
+    /// Goes through its own body and through bushes.
+    Ghost,
+    /// Pulls the nearby apples toward the head.
+    Magnet,
+    /// Slows the snake down.
+    SlowMotion,
+    /// Multiplies the points earned by every apple, by one more per level.
+    ScoreMultiplier,
+    /// Absorbs the next collision.
+    Shield,
+}
+
+impl PowerUp {
+    pub const ALL: [Self; 5] = [
+        Self::Ghost,
+        Self::Magnet,
+        Self::SlowMotion,
+        Self::ScoreMultiplier,
+        Self::Shield,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Ghost => "Ghost",
+            Self::Magnet => "Magnet",
+            Self::SlowMotion => "Slow motion",
+            Self::ScoreMultiplier => "Score multiplier",
+            Self::Shield => "Shield",
+        }
+    }
+
+    /// Duration of the effect, in seconds.
+    pub const fn duration(self) -> f32 {
+        match self {
+            Self::Ghost => 6.0,
+            Self::Magnet => 8.0,
+            Self::SlowMotion => 5.0,
+            Self::ScoreMultiplier => 10.0,
+            Self::Shield => 15.0,
+        }
+    }
+
+    pub const fn stacking(self) -> Stacking {
+        match self {
+            Self::Ghost => Stacking::Duration(12.0),
+            Self::Magnet => Stacking::Duration(16.0),
+            Self::SlowMotion => Stacking::Duration(10.0),
+            Self::ScoreMultiplier => Stacking::Level(3),
+            // a single collision is absorbed however many shields are picked
+            Self::Shield => Stacking::Level(1),
+        }
+    }
+
+    /// Color of the power-up, its indicator and the snakes under its effect.
+    pub const fn color(self) -> Color {
+        match self {
+            Self::Ghost => Color::rgb(0.75, 0.85, 1.0),
+            Self::Magnet => Color::rgb(0.9, 0.3, 0.3),
+            Self::SlowMotion => Color::rgb(0.4, 0.6, 1.0),
+            Self::ScoreMultiplier => Color::rgb(1.0, 0.5, 0.9),
+            Self::Shield => Color::rgb(0.3, 0.9, 0.6),
+        }
+    }
+
+    /// Returns the color of the snakes under the effect of the given power-ups, a ghost being see-through.
+    pub fn tint(power_ups: &[Self]) -> Color {
+        if power_ups.is_empty() {
+            return Color::WHITE;
+        }
+
+        let sum = power_ups
+            .iter()
+            .fold(Vec4::ZERO, |sum, power_up| sum + Vec4::from(power_up.color()));
+        // only half tinted, so that the skin still shows through
+        let color = (sum / power_ups.len() as f32 + Vec4::ONE) / 2.0;
+        let alpha = if power_ups.contains(&Self::Ghost) { 0.5 } else { 1.0 };
+
+        Color::rgba(color.x, color.y, color.z, alpha)
+    }
+
+    /// Grants the effect of the power-up to the given snake, at the given level and for the given lifetime.
+    pub fn insert_effect(self, commands: &mut Commands, snake: Entity, level: u8, lifetime: Lifetime) {
+        let mut commands = commands.entity(snake);
+
+        match self {
+            Self::Ghost => commands.insert(TimedEffect::<Ghost>::new(level, lifetime)),
+            Self::Magnet => commands.insert(TimedEffect::<Magnet>::new(level, lifetime)),
+            Self::SlowMotion => commands.insert(TimedEffect::<SlowMotion>::new(level, lifetime)),
+            Self::ScoreMultiplier => commands.insert(TimedEffect::<ScoreMultiplier>::new(level, lifetime)),
+            Self::Shield => commands.insert(TimedEffect::<Shield>::new(level, lifetime)),
+        };
+    }
+
+    /// Takes every effect of a power-up away from the given snake.
+    pub fn remove_effects(commands: &mut Commands, snake: Entity) {
+        commands
+            .entity(snake)
+            .remove::<TimedEffect<Ghost>>()
+            .remove::<TimedEffect<Magnet>>()
+            .remove::<TimedEffect<SlowMotion>>()
+            .remove::<TimedEffect<ScoreMultiplier>>()
+            .remove::<TimedEffect<Shield>>();
+    }
+}
+
+/// Kind of effect granted by a power-up, used to tell the [`TimedEffect`] components apart.
+pub trait Effect: Send + Sync + 'static {
+    const POWER_UP: PowerUp;
+}
+
+#[derive(Debug)]
+pub struct Ghost;
+
+#[derive(Debug)]
+pub struct Magnet;
+
+#[derive(Debug)]
+pub struct SlowMotion;
+
+#[derive(Debug)]
+pub struct ScoreMultiplier;
+
+#[derive(Debug)]
+pub struct Shield;
+
+impl Effect for Ghost {
+    const POWER_UP: PowerUp = PowerUp::Ghost;
+}
+
+impl Effect for Magnet {
+    const POWER_UP: PowerUp = PowerUp::Magnet;
+}
+
+impl Effect for SlowMotion {
+    const POWER_UP: PowerUp = PowerUp::SlowMotion;
+}
+
+impl Effect for ScoreMultiplier {
+    const POWER_UP: PowerUp = PowerUp::ScoreMultiplier;
+}
+
+impl Effect for Shield {
+    const POWER_UP: PowerUp = PowerUp::Shield;
+}
+
+/// Effect of a power-up on a snake, removed once its lifetime is over.
+#[derive(Debug, Component)]
+pub struct TimedEffect<T: Effect> {
+    lifetime: Lifetime,
+    level: u8,
+    marker: PhantomData<T>,
+}
+
+impl<T: Effect> Default for TimedEffect<T> {
+    fn default() -> Self {
+        Self::new(1, Lifetime::from_seconds(T::POWER_UP.duration()))
+    }
+}
+
+impl<T: Effect> TimedEffect<T> {
+    pub const fn new(level: u8, lifetime: Lifetime) -> Self {
+        Self {
+            lifetime,
+            level,
+            marker: PhantomData,
+        }
+    }
+
+    pub const fn level(&self) -> u8 {
+        self.level
+    }
+
+    pub fn to_active_effect(&self) -> ActiveEffect {
+        ActiveEffect {
+            power_up: T::POWER_UP,
+            level: self.level,
+            lifetime: self.lifetime.clone(),
+        }
+    }
+
+    /// Returns the time left before the effect wears off, in seconds.
+    pub fn remaining(&self) -> f32 {
+        self.duration().saturating_sub(self.elapsed()).as_secs_f32()
+    }
+
+    /// Adds the effect of another power-up of the same kind, following its [`Stacking`].
+    pub fn stack(&mut self) {
+        let power_up = T::POWER_UP;
+
+        match power_up.stacking() {
+            Stacking::Duration(max) => {
+                self.lifetime = Lifetime::from_seconds((self.remaining() + power_up.duration()).min(max));
+            }
+            Stacking::Level(max) => {
+                self.level = (self.level + 1).min(max);
+                self.lifetime = Lifetime::from_seconds(power_up.duration());
+            }
+        }
+    }
+}
+
+impl TimedEffect<ScoreMultiplier> {
+    /// Returns the points earned by every apple, two at the first level.
+    pub fn multiplier(&self) -> u32 {
+        u32::from(self.level) + 1
+    }
+}
+
+impl<T: Effect> Deref for TimedEffect<T> {
+    type Target = Timer;
+
+    fn deref(&self) -> &Self::Target {
+        &self.lifetime
+    }
+}
+
+impl<T: Effect> DerefMut for TimedEffect<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.lifetime
+    }
+}
+
+/// Effect of a power-up active on a snake, whatever its kind, to grant it again later on.
+#[derive(Debug, Clone)]
+pub struct ActiveEffect {
+    pub power_up: PowerUp,
+    pub level: u8,
+    pub lifetime: Lifetime,
+}
+
+impl ActiveEffect {
+    pub fn insert(self, commands: &mut Commands, snake: Entity) {
+        self.power_up.insert_effect(commands, snake, self.level, self.lifetime);
+    }
+}
+
+/// Reads the effects active on the snakes, whatever their kind.
+#[derive(SystemParam)]
+pub struct ActiveEffects<'w, 's> {
+    ghost: Query<'w, 's, &'static TimedEffect<Ghost>>,
+    magnet: Query<'w, 's, &'static TimedEffect<Magnet>>,
+    slow_motion: Query<'w, 's, &'static TimedEffect<SlowMotion>>,
+    score_multiplier: Query<'w, 's, &'static TimedEffect<ScoreMultiplier>>,
+    shield: Query<'w, 's, &'static TimedEffect<Shield>>,
+}
+
+impl ActiveEffects<'_, '_> {
+    /// Returns the effects active on the given snake, in the order of [`PowerUp::ALL`].
+    pub fn get(&self, snake: Entity) -> Vec<ActiveEffect> {
+        [
+            self.ghost.get(snake).ok().map(TimedEffect::to_active_effect),
+            self.magnet.get(snake).ok().map(TimedEffect::to_active_effect),
+            self.slow_motion.get(snake).ok().map(TimedEffect::to_active_effect),
+            self.score_multiplier.get(snake).ok().map(TimedEffect::to_active_effect),
+            self.shield.get(snake).ok().map(TimedEffect::to_active_effect),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+/// Power-up lying on the map, waiting to be picked by a snake.
+#[derive(Debug, Component)]
+pub struct PowerUpItem(pub PowerUp);
+
+/// A snake picked a power-up up.
+#[derive(Debug)]
+pub struct PowerUpCollected {
+    pub snake: Entity,
+    pub power_up: PowerUp,
+}
+
+#[derive(Debug, Clone)]
+pub struct PowerUpSpawner {
+    pub timer: Timer,
+    pub max_power_ups: usize,
+    pub power_up_lifetime: Lifetime,
+}
+
+/// Grants, stacks and wears off the `T` effect of the snakes.
+pub struct EffectPlugin<T> {
+    marker: PhantomData<T>,
+}
+
+impl<T> Default for EffectPlugin<T> {
+    fn default() -> Self {
+        Self { marker: PhantomData }
+    }
+}
+
+impl<T: Effect> Plugin for EffectPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_system(apply_effect::<T>.after(GameSystem::CollisionDetection))
+            .add_system(systems::update_timer::<TimedEffect<T>>.run_in_state(GameStatus::Running))
+            .add_system(wear_off_effect::<T>);
+    }
+}
+
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PowerUpCollected>()
+            .add_plugin(EffectPlugin::<Ghost>::default())
+            .add_plugin(EffectPlugin::<Magnet>::default())
+            .add_plugin(EffectPlugin::<SlowMotion>::default())
+            .add_plugin(EffectPlugin::<ScoreMultiplier>::default())
+            .add_plugin(EffectPlugin::<Shield>::default())
+            .add_system(
+                spawn_power_up
+                    .run_if_resource_exists::<PowerUpSpawner>()
+                    .run_if_resource_exists::<TileMap>()
+                    // both draw from the game RNG, in a fixed order to keep seeded games reproducible
+                    .after(GameSystem::SpawnApple)
+                    .before(GameSystem::Movement),
+            )
+            .add_system(
+                attract_apples
+                    .run_if_resource_exists::<TileMap>()
+                    .before(GameSystem::Movement),
+            )
+            .add_system(
+                collect_power_ups
+                    .after(GameSystem::CheckPosition)
+                    .label(GameSystem::CollisionDetection),
+            )
+            .add_system(remove_power_ups)
+            .add_system_to_stage(GameStage::Transform, tint_snakes);
+    }
+}
+
+fn spawn_power_up(
+    mut commands: Commands,
+    query: Query<&PowerUpItem>,
+    time: Res<Time>,
+    mut power_up_spawner: ResMut<PowerUpSpawner>,
+    tile_map: Res<TileMap>,
+    mut rng: ResMut<GameRng>,
+) {
+    if !power_up_spawner.timer.tick(time.delta()).just_finished() || query.iter().count() >= power_up_spawner.max_power_ups
+    {
+        return;
+    }
+
+    let tiles = tile_map.tiles().filter(|tile| tile.is_empty()).collect::<Vec<_>>();
+
+    if tiles.is_empty() {
+        return;
+    }
+
+    let position = tiles[rng.gen_range(0..tiles.len())].position();
+    let power_up = PowerUp::ALL[rng.gen_range(0..PowerUp::ALL.len())];
+
+    spawn_power_up_item(
+        &mut commands,
+        power_up,
+        IVec3::new(position.x as i32, position.y as i32, 1),
+        power_up_spawner.power_up_lifetime.clone(),
+    );
+}
+
+pub fn spawn_power_up_item(commands: &mut Commands, power_up: PowerUp, position: IVec3, lifetime: Lifetime) -> Entity {
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: power_up.color(),
+                ..default()
+            },
+            // a diamond, standing out from the round apples
+            transform: Transform::from_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+            ..default()
+        })
+        .insert_bundle((
+            PowerUpItem(power_up),
+            lifetime,
+            RigidBody,
+            Position(position),
+            Animator::new(Tween::new(
+                EaseFunction::SineInOut,
+                TweeningType::PingPong,
+                Duration::from_millis(600),
+                TransformScaleLens {
+                    start: Vec3::new(0.4, 0.4, 1.0),
+                    end: Vec3::new(0.5, 0.5, 1.0),
+                },
+            )),
+            Name::new(format!("Power-up ({})", power_up.name())),
+        ))
+        .id()
+}
+
+/// Removes the power-ups left on the map for too long.
+fn remove_power_ups(mut commands: Commands, query: Query<(Entity, &Lifetime), With<PowerUpItem>>) {
+    query.for_each(|(entity, lifetime)| {
+        if lifetime.just_finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    });
+}
+
+fn collect_power_ups(
+    mut commands: Commands,
+    mut collected_writer: EventWriter<PowerUpCollected>,
+    mut sound_writer: EventWriter<SoundEffect>,
+    snake_query: Query<(Entity, &Snake), Without<Dead>>,
+    position_query: Query<&Position>,
+    power_up_query: Query<(Entity, &Position, &PowerUpItem)>,
+) {
+    snake_query.for_each(|(snake_entity, snake)| {
+        let Ok(head_position) = position_query.get(snake.fragments[0]) else {
+            return;
+        };
+
+        power_up_query.for_each(|(entity, position, item)| {
+            if position.xy() == head_position.xy() {
+                commands.entity(entity).despawn_recursive();
+
+                collected_writer.send(PowerUpCollected {
+                    snake: snake_entity,
+                    power_up: item.0,
+                });
+                sound_writer.send(SoundEffect::Eat);
+            }
+        });
+    });
+}
+
+/// Grants the effect of the power-ups picked up, stacking it with the one already active.
+fn apply_effect<T: Effect>(
+    mut commands: Commands,
+    mut collected_reader: EventReader<PowerUpCollected>,
+    mut effect_query: Query<&mut TimedEffect<T>>,
+) {
+    for event in collected_reader.iter().filter(|event| event.power_up == T::POWER_UP) {
+        match effect_query.get_mut(event.snake) {
+            Ok(mut effect) => effect.stack(),
+            Err(_) => {
+                commands.entity(event.snake).insert(TimedEffect::<T>::default());
+            }
+        }
+    }
+}
+
+fn wear_off_effect<T: Effect>(mut commands: Commands, query: Query<(Entity, &TimedEffect<T>)>) {
+    query.for_each(|(entity, effect)| {
+        if effect.finished() {
+            commands.entity(entity).remove::<TimedEffect<T>>();
+        }
+    });
+}
+
+/// Moves the apples within the range of a magnet one tile closer to the head, every time the snake moves.
+fn attract_apples(
+    snake_query: Query<(&Snake, &MoveTimer), (With<TimedEffect<Magnet>>, Without<Dead>)>,
+    head_query: Query<&Position, Without<Apple>>,
+    mut apple_query: Query<&mut Position, With<Apple>>,
+    tile_map: Res<TileMap>,
+) {
+    snake_query.for_each(|(snake, timer)| {
+        if !timer.just_finished() {
+            return;
+        }
+
+        let head = match head_query.get(snake.fragments[0]) {
+            Ok(position) => position.xy(),
+            Err(_) => return,
+        };
+
+        apple_query.for_each_mut(|mut position| {
+            let offset = head - position.xy();
+
+            if offset.x.abs().max(offset.y.abs()) > MAGNET_RANGE {
+                return;
+            }
+
+            // one step along the longest axis, so that the apple ends up in front of the snake
+            let step = if offset.x.abs() >= offset.y.abs() {
+                IVec2::new(offset.x.signum(), 0)
+            } else {
+                IVec2::new(0, offset.y.signum())
+            };
+            let target = position.xy() + step;
+
+            let is_free = tile_map.tile_under(target).is_some_and(Tile::is_empty);
+
+            if step != IVec2::ZERO && is_free {
+                position.0 += step.extend(0);
+            }
+        });
+    });
+}
+
+/// Colors the fragments of the snakes after their active effects, leaving the dying ones to their death sequence.
+fn tint_snakes(
+    snake_query: Query<
+        (
+            &Snake,
+            Option<&TimedEffect<Ghost>>,
+            Option<&TimedEffect<Magnet>>,
+            Option<&TimedEffect<SlowMotion>>,
+            Option<&TimedEffect<ScoreMultiplier>>,
+            Option<&TimedEffect<Shield>>,
+        ),
+        Without<Dead>,
+    >,
+    mut sprite_query: Query<&mut TextureAtlasSprite>,
+) {
+    snake_query.for_each(|(snake, ghost, magnet, slow_motion, score_multiplier, shield)| {
+        let power_ups = [
+            ghost.map(|_| PowerUp::Ghost),
+            magnet.map(|_| PowerUp::Magnet),
+            slow_motion.map(|_| PowerUp::SlowMotion),
+            score_multiplier.map(|_| PowerUp::ScoreMultiplier),
+            shield.map(|_| PowerUp::Shield),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        let tint = PowerUp::tint(&power_ups);
+
+        let mut iter = sprite_query.iter_many_mut(&snake.fragments);
+
+        while let Some(mut sprite) = iter.fetch_next() {
+            if sprite.color != tint {
+                sprite.color = tint;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::prelude::*;
+
+    use super::{Ghost, PowerUp, ScoreMultiplier, Shield, TimedEffect};
+
+    #[test]
+    fn it_adds_up_durations_to_a_limit() {
+        let mut effect = TimedEffect::<Ghost>::default();
+
+        effect.tick(Duration::from_secs(2));
+        effect.stack();
+        assert!((effect.remaining() - 10.0).abs() < 1e-3);
+
+        effect.stack();
+        assert!((effect.remaining() - 12.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn it_raises_levels_to_a_limit() {
+        let mut multiplier = TimedEffect::<ScoreMultiplier>::default();
+
+        multiplier.tick(Duration::from_secs(4));
+        multiplier.stack();
+        assert_eq!(multiplier.multiplier(), 3);
+        assert!((multiplier.remaining() - 10.0).abs() < 1e-3);
+
+        multiplier.stack();
+        multiplier.stack();
+        assert_eq!(multiplier.multiplier(), 4);
+
+        let mut shield = TimedEffect::<Shield>::default();
+        shield.stack();
+        assert_eq!(shield.level(), 1);
+    }
+
+    #[test]
+    fn it_tints_snakes_after_their_effects() {
+        assert_eq!(PowerUp::tint(&[]), Color::WHITE);
+        assert!((PowerUp::tint(&[PowerUp::Ghost]).a() - 0.5).abs() < 1e-6);
+        assert!(Vec4::from(PowerUp::tint(&[PowerUp::Shield])).abs_diff_eq(Vec4::new(0.65, 0.95, 0.8, 1.0), 1e-6));
+    }
+}
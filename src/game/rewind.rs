@@ -0,0 +1,474 @@
+use std::collections::VecDeque;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use super::apple::{Apple, AppleBuilder, GoldenApple};
+use super::bush::{self, Bush, BushBuilder, BushCutter, Stump};
+use super::components::{Direction, Lifetime, Position};
+use super::daily::DailyRun;
+use super::level::LevelEntity;
+use super::power_up::{self, ActiveEffect, ActiveEffects, PowerUp, PowerUpItem};
+use super::score::Score;
+use super::snake::{self, Dead, MoveTimer, Snake, SnakeFragment};
+use super::value_tracker::{PreviousValue, ValueHistory, DEFAULT_HISTORY_DEPTH};
+use super::{GameStage, GameSystem};
+use crate::assets::TextureAssets;
+use crate::settings::Settings;
+use crate::states::GameStatus;
+
+#[derive(Debug)]
+pub struct RewindSettings {
+    /// Number of ticks kept in the [`RewindBuffer`].
+    pub depth: usize,
+    /// Number of ticks rolled back by the rewind key, available in practice mode.
+    pub ticks: usize,
+}
+
+impl Default for RewindSettings {
+    fn default() -> Self {
+        Self {
+            depth: 200,
+            ticks: 16,
+        }
+    }
+}
+
+/// Rolls the game back the given number of ticks, or as far as the [`RewindBuffer`] goes.
+#[derive(Debug, Copy, Clone)]
+pub struct Rewind(pub usize);
+
+#[derive(Debug, Clone)]
+struct FragmentSnapshot {
+    fragment: SnakeFragment,
+    position: Position,
+    direction: Direction,
+    previous_direction: Option<Direction>,
+}
+
+#[derive(Debug, Clone)]
+struct SnakeSnapshot {
+    entity: Entity,
+    fragments: Vec<FragmentSnapshot>,
+    last_known_tail: Option<(Position, Direction)>,
+    bush_cutter_charges: u32,
+    effects: Vec<ActiveEffect>,
+}
+
+#[derive(Debug, Clone)]
+struct AppleSnapshot {
+    position: Position,
+    /// Rotation of the apple, in degrees.
+    angle: f32,
+    is_golden: bool,
+    lifetime: Option<Lifetime>,
+}
+
+#[derive(Debug, Clone)]
+struct BushSnapshot {
+    position: Position,
+    /// Rotation of the bush, in degrees.
+    angle: f32,
+}
+
+#[derive(Debug, Clone)]
+struct StumpSnapshot {
+    position: Position,
+    /// Rotation of the destroyed bush, in degrees.
+    angle: f32,
+    regrow: Option<Timer>,
+}
+
+#[derive(Debug, Clone)]
+struct PowerUpSnapshot {
+    power_up: PowerUp,
+    position: Position,
+    lifetime: Lifetime,
+}
+
+/// State of the simulation at the end of a tick.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    snakes: Vec<SnakeSnapshot>,
+    apples: Vec<AppleSnapshot>,
+    /// Bushes standing at the time, so that the ones cut down afterwards stand again once rewound.
+    bushes: Vec<BushSnapshot>,
+    stumps: Vec<StumpSnapshot>,
+    power_ups: Vec<PowerUpSnapshot>,
+    score: Score,
+}
+
+/// Snapshots taken every time the snakes move, newest first.
+#[derive(Debug, Default)]
+pub struct RewindBuffer {
+    snapshots: VecDeque<Snapshot>,
+}
+
+impl RewindBuffer {
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
+
+pub struct RewindPlugin;
+
+impl Plugin for RewindPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RewindSettings>()
+            .add_event::<Rewind>()
+            .add_system_set(
+                ConditionSet::new()
+                    .run_if_resource_exists::<RewindBuffer>()
+                    .before(GameSystem::Movement)
+                    .with_system(rewind_on_key)
+                    .with_system(rewind)
+                    .into(),
+            )
+            .add_system_to_stage(
+                GameStage::Transform,
+                record_snapshot.run_if_resource_exists::<RewindBuffer>(),
+            );
+    }
+}
+
+fn rewind_on_key(
+    keys: Res<Input<KeyCode>>,
+    settings: Res<Settings>,
+    rewind_settings: Res<RewindSettings>,
+    status: Res<CurrentState<GameStatus>>,
+    daily_run: Option<Res<DailyRun>>,
+    mut rewind_writer: EventWriter<Rewind>,
+) {
+    // rewinding is allowed while playing, and from the game over overlay to go back before the fatal collision
+    let can_rewind = matches!(status.0, GameStatus::Running | GameStatus::GameOver)
+        && DailyRun::allows_retries(daily_run.as_deref());
+
+    if settings.practice_mode && can_rewind && keys.just_pressed(KeyCode::Back) {
+        rewind_writer.send(Rewind(rewind_settings.ticks));
+    }
+}
+
+/// Snakes as recorded in the snapshots.
+#[derive(SystemParam)]
+struct RecordedSnakes<'w, 's> {
+    snakes: Query<'w, 's, (Entity, &'static Snake, &'static MoveTimer, &'static BushCutter)>,
+    dead: Query<'w, 's, (), (With<Snake>, With<Dead>)>,
+    fragments: Query<
+        'w,
+        's,
+        (
+            &'static SnakeFragment,
+            &'static Position,
+            &'static Direction,
+            Option<&'static PreviousValue<Direction>>,
+        ),
+    >,
+    effects: ActiveEffects<'w, 's>,
+}
+
+/// Items lying on the map as recorded in the snapshots.
+#[derive(SystemParam)]
+struct RecordedItems<'w, 's> {
+    apples: Query<
+        'w,
+        's,
+        (
+            &'static Position,
+            &'static Transform,
+            Option<&'static GoldenApple>,
+            Option<&'static Lifetime>,
+        ),
+        With<Apple>,
+    >,
+    bushes: Query<'w, 's, (&'static Bush, &'static Position)>,
+    stumps: Query<'w, 's, (&'static Stump, &'static Position)>,
+    power_ups: Query<'w, 's, (&'static PowerUpItem, &'static Position, &'static Lifetime)>,
+}
+
+/// Takes a snapshot once every snake moved and settled, i.e. once the fragments they grew have been spawned.
+///
+/// Recording stops as soon as a snake dies, so that rewinding always goes back to before the fatal collision.
+fn record_snapshot(
+    mut buffer: ResMut<RewindBuffer>,
+    settings: Res<RewindSettings>,
+    snakes: RecordedSnakes,
+    items: RecordedItems,
+    score: Option<Res<Score>>,
+) {
+    if !snakes.dead.is_empty() || !snakes.snakes.iter().any(|(_, _, timer, _)| timer.just_finished()) {
+        return;
+    }
+
+    let snake_snapshots = snakes
+        .snakes
+        .iter()
+        .map(|(entity, snake, _, bush_cutter)| SnakeSnapshot {
+            entity,
+            fragments: snakes
+                .fragments
+                .iter_many(&snake.fragments)
+                .map(|(fragment, position, direction, previous_direction)| FragmentSnapshot {
+                    fragment: *fragment,
+                    position: *position,
+                    direction: *direction,
+                    previous_direction: previous_direction.map(|previous_direction| previous_direction.0),
+                })
+                .collect(),
+            last_known_tail: snake.last_known_tail,
+            bush_cutter_charges: bush_cutter.charges,
+            effects: snakes.effects.get(entity),
+        })
+        .collect();
+
+    let apples = items
+        .apples
+        .iter()
+        .map(|(position, transform, golden_apple, lifetime)| {
+            let (axis, angle) = transform.rotation.to_axis_angle();
+
+            AppleSnapshot {
+                position: *position,
+                angle: (angle * axis.z).to_degrees(),
+                is_golden: golden_apple.is_some(),
+                lifetime: lifetime.cloned(),
+            }
+        })
+        .collect();
+
+    let bushes = items
+        .bushes
+        .iter()
+        .map(|(bush, position)| BushSnapshot {
+            position: *position,
+            angle: bush.angle,
+        })
+        .collect();
+
+    let stumps = items
+        .stumps
+        .iter()
+        .map(|(stump, position)| StumpSnapshot {
+            position: *position,
+            angle: stump.angle(),
+            regrow: stump.regrow_timer().cloned(),
+        })
+        .collect();
+
+    let power_ups = items
+        .power_ups
+        .iter()
+        .map(|(item, position, lifetime)| PowerUpSnapshot {
+            power_up: item.0,
+            position: *position,
+            lifetime: lifetime.clone(),
+        })
+        .collect();
+
+    buffer.snapshots.push_front(Snapshot {
+        snakes: snake_snapshots,
+        apples,
+        bushes,
+        stumps,
+        power_ups,
+        score: score.map(|score| *score).unwrap_or_default(),
+    });
+    buffer.snapshots.truncate(settings.depth.max(1));
+}
+
+/// Snakes brought back to the state of a snapshot.
+#[derive(SystemParam)]
+struct RewoundSnakes<'w, 's> {
+    snakes: Query<'w, 's, (&'static mut Snake, &'static mut MoveTimer, &'static mut BushCutter)>,
+    fragments: Query<
+        'w,
+        's,
+        (
+            &'static mut SnakeFragment,
+            &'static mut Position,
+            &'static mut Direction,
+            Option<&'static mut ValueHistory<Direction>>,
+        ),
+    >,
+    time: Res<'w, Time>,
+}
+
+impl RewoundSnakes<'_, '_> {
+    fn restore(&mut self, commands: &mut Commands, snake_snapshot: &SnakeSnapshot) {
+        let Ok((mut snake, mut timer, mut bush_cutter)) = self.snakes.get_mut(snake_snapshot.entity) else {
+            return;
+        };
+        let timestamp = self.time.seconds_since_startup();
+
+        // drop the fragments grown since the snapshot
+        let length = snake_snapshot.fragments.len().min(snake.fragments.len());
+
+        snake
+            .fragments
+            .split_off(length)
+            .into_iter()
+            .for_each(|entity| commands.entity(entity).despawn_recursive());
+
+        for (i, fragment_snapshot) in snake_snapshot.fragments.iter().enumerate() {
+            let entity = if let Some(entity) = snake.fragments.get(i) {
+                *entity
+            } else {
+                let entity = snake::spawn_snake_fragment(
+                    commands,
+                    fragment_snapshot.position.0,
+                    fragment_snapshot.direction,
+                    fragment_snapshot.fragment,
+                );
+
+                snake.fragments.push(entity);
+
+                entity
+            };
+
+            // the value tracker records the restored direction on top of the previous one left in the history
+            let mut restored_history = ValueHistory::new(DEFAULT_HISTORY_DEPTH);
+
+            if let Some(previous_direction) = fragment_snapshot.previous_direction {
+                restored_history.push(timestamp, previous_direction);
+            } else {
+                commands.entity(entity).remove::<PreviousValue<Direction>>();
+            }
+
+            if let Ok((mut fragment, mut position, mut direction, history)) = self.fragments.get_mut(entity) {
+                *fragment = fragment_snapshot.fragment;
+                *position = fragment_snapshot.position;
+                *direction = fragment_snapshot.direction;
+
+                if let Some(mut history) = history {
+                    *history = restored_history;
+                }
+            } else {
+                commands.entity(entity).insert(restored_history);
+            }
+        }
+
+        // going back before a fatal collision brings the snake back to life
+        commands.entity(snake_snapshot.entity).remove::<Dead>();
+
+        // the effects picked since the snapshot are lost, and the ones worn off since are granted again
+        PowerUp::remove_effects(commands, snake_snapshot.entity);
+
+        for effect in &snake_snapshot.effects {
+            effect.clone().insert(commands, snake_snapshot.entity);
+        }
+
+        snake.next_direction = None;
+        snake.last_known_tail = snake_snapshot.last_known_tail;
+        bush_cutter.charges = snake_snapshot.bush_cutter_charges;
+
+        // wait for a full tick before moving again, the restored fragments are not all spawned yet
+        timer.reset();
+    }
+}
+
+/// Items lying on the map, respawned as they were in a snapshot.
+#[derive(SystemParam)]
+struct RewoundItems<'w, 's> {
+    items: Query<'w, 's, Entity, Or<(With<Apple>, With<Bush>, With<Stump>, With<PowerUpItem>)>>,
+    textures: Res<'w, TextureAssets>,
+}
+
+impl RewoundItems<'_, '_> {
+    fn restore(&self, commands: &mut Commands, snapshot: &Snapshot) {
+        self.items
+            .for_each(|entity| commands.entity(entity).despawn_recursive());
+
+        for apple_snapshot in &snapshot.apples {
+            let mut apple_builder = AppleBuilder::default()
+                .with_position(apple_snapshot.position.0)
+                .with_angle(apple_snapshot.angle);
+
+            if apple_snapshot.is_golden {
+                apple_builder = apple_builder.golden();
+            }
+
+            let entity = apple_builder.spawn(commands, &self.textures);
+
+            if let Some(lifetime) = &apple_snapshot.lifetime {
+                commands.entity(entity).insert(lifetime.clone());
+            }
+        }
+
+        // bring back the bushes cut down since the snapshot, and the stumps of the ones cut down before
+        for bush_snapshot in &snapshot.bushes {
+            let entity = BushBuilder::default()
+                .with_position(bush_snapshot.position.0)
+                .with_angle(bush_snapshot.angle)
+                .spawn(commands, &self.textures);
+
+            commands.entity(entity).insert(LevelEntity);
+        }
+
+        for stump_snapshot in &snapshot.stumps {
+            bush::spawn_stump(
+                commands,
+                &self.textures,
+                stump_snapshot.position.0,
+                stump_snapshot.angle,
+                stump_snapshot.regrow.clone(),
+            );
+        }
+
+        for power_up_snapshot in &snapshot.power_ups {
+            power_up::spawn_power_up_item(
+                commands,
+                power_up_snapshot.power_up,
+                power_up_snapshot.position.0,
+                power_up_snapshot.lifetime.clone(),
+            );
+        }
+    }
+}
+
+fn rewind(
+    mut commands: Commands,
+    mut rewind_reader: EventReader<Rewind>,
+    mut buffer: ResMut<RewindBuffer>,
+    mut snakes: RewoundSnakes,
+    items: RewoundItems,
+    score: Option<ResMut<Score>>,
+    status: Res<CurrentState<GameStatus>>,
+) {
+    let ticks = rewind_reader.iter().map(|rewind| rewind.0).sum::<usize>();
+
+    // the newest snapshot is the current state, so there must be an older one to go back to
+    let ticks = ticks.min(buffer.len().saturating_sub(1));
+
+    if ticks == 0 {
+        return;
+    }
+
+    buffer.snapshots.drain(..ticks);
+
+    let Some(snapshot) = buffer.snapshots.front() else {
+        return;
+    };
+
+    for snake_snapshot in &snapshot.snakes {
+        snakes.restore(&mut commands, snake_snapshot);
+    }
+
+    items.restore(&mut commands, snapshot);
+
+    if let Some(mut score) = score {
+        *score = snapshot.score;
+    }
+
+    // the snakes are alive again, leave the game over overlay
+    if status.0 == GameStatus::GameOver {
+        commands.insert_resource(NextState(GameStatus::Running));
+    }
+}
@@ -0,0 +1,739 @@
+use std::time::Duration;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use derive_more::Display;
+use iyes_loopless::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::animation::Swallow;
+use super::apple::{Apple, AppleBuilder, AppleSequence, AppleSpawner, GoldenApple};
+use super::bush::{self, Bush, BushCutter, Stump};
+use super::components::{Direction, Lifetime, NumberGenerator, Position};
+use super::level::{self, ActiveLevel, Level, LevelEntity, LevelTile, TileEntity};
+use super::play_mode::{ModeClock, PlayMode};
+use super::power_up::{self, ActiveEffect, ActiveEffects, PowerUp, PowerUpItem, PowerUpSpawner};
+use super::rewind::RewindBuffer;
+use super::rng::GameRng;
+use super::score::Score;
+use super::snake::{self, MoveTimer, Snake, SnakeFragment};
+use super::value_tracker::{PreviousValue, ValueHistory, DEFAULT_HISTORY_DEPTH};
+use super::wind::{Wind, WindTimer};
+use crate::assets::TextureAssets;
+use crate::states::GameStatus;
+use crate::storage::{self, StorageError};
+
+/// Version of the save format, to be bumped on every incompatible change.
+pub const SAVE_VERSION: u32 = 3;
+pub const QUICK_SAVE_FILE: &str = "quick_save.json";
+
+#[derive(Debug, Display)]
+pub enum SaveError {
+    #[display(fmt = "no saved game")]
+    Missing,
+    #[display(fmt = "{_0}")]
+    Storage(StorageError),
+    #[display(fmt = "unsupported saved game version {_0} (expected {SAVE_VERSION})")]
+    Version(u64),
+    #[display(fmt = "corrupted saved game: {_0}")]
+    Invalid(serde_json::Error),
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<StorageError> for SaveError {
+    fn from(error: StorageError) -> Self {
+        Self::Storage(error)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimerState {
+    pub duration: f32,
+    pub elapsed: f32,
+}
+
+impl TimerState {
+    fn capture(timer: &Timer) -> Self {
+        Self {
+            duration: timer.duration().as_secs_f32(),
+            elapsed: timer.elapsed_secs(),
+        }
+    }
+
+    fn restore(self, repeating: bool) -> Timer {
+        let mut timer = Timer::from_seconds(self.duration, repeating);
+        timer.set_elapsed(Duration::from_secs_f32(self.elapsed.min(self.duration)));
+
+        timer
+    }
+
+    fn restore_lifetime(self) -> Lifetime {
+        let mut lifetime = Lifetime::from_seconds(self.duration);
+        lifetime.set_elapsed(Duration::from_secs_f32(self.elapsed.min(self.duration)));
+
+        lifetime
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FragmentState {
+    pub fragment: SnakeFragment,
+    pub position: [i32; 3],
+    pub direction: Direction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_direction: Option<Direction>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnakeState {
+    pub fragments: Vec<FragmentState>,
+    pub next_direction: Option<Direction>,
+    pub last_known_tail: Option<([i32; 3], Direction)>,
+    pub move_timer: TimerState,
+    pub bush_cutter_charges: u32,
+    pub fragment_generator: NumberGenerator<u16>,
+    pub effects: Vec<EffectState>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EffectState {
+    pub power_up: PowerUp,
+    pub level: u8,
+    pub lifetime: TimerState,
+}
+
+impl EffectState {
+    fn capture(effect: &ActiveEffect) -> Self {
+        Self {
+            power_up: effect.power_up,
+            level: effect.level,
+            lifetime: TimerState::capture(&effect.lifetime),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppleState {
+    pub position: [i32; 3],
+    /// Rotation of the apple, in degrees.
+    pub angle: f32,
+    pub is_golden: bool,
+    pub lifetime: Option<TimerState>,
+}
+
+/// Pace of the apples, as adapted to the mode or to the campaign level.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppleSpawnerState {
+    pub timer: TimerState,
+    pub max_apples: usize,
+    /// Lifetime of the apples, in seconds, if they rot.
+    pub apple_lifetime: Option<f32>,
+    pub golden_apple_chance: f64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PowerUpState {
+    pub power_up: PowerUp,
+    pub position: [i32; 3],
+    pub lifetime: TimerState,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PowerUpSpawnerState {
+    pub timer: TimerState,
+    pub max_power_ups: usize,
+    /// Time a power-up stays on the map, in seconds.
+    pub power_up_lifetime: f32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StumpState {
+    pub position: [i32; 3],
+    /// Rotation of the destroyed bush, in degrees.
+    pub angle: f32,
+    pub regrow: Option<TimerState>,
+}
+
+/// Everything needed to resume a game exactly where it was left.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveGame {
+    pub version: u32,
+    /// Level as it currently stands, i.e. without the bushes destroyed so far.
+    pub level: Level,
+    pub stumps: Vec<StumpState>,
+    pub snakes: Vec<SnakeState>,
+    pub apples: Vec<AppleState>,
+    pub apple_spawner: AppleSpawnerState,
+    /// Apples still to come in a daily challenge.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apple_sequence: Option<AppleSequence>,
+    pub power_ups: Vec<PowerUpState>,
+    /// Absent from the games without power-ups, such as the tutorial.
+    pub power_up_spawner: Option<PowerUpSpawnerState>,
+    pub wind_timer: Option<TimerState>,
+    pub mode: PlayMode,
+    /// Time left in a time attack.
+    pub mode_clock: Option<TimerState>,
+    pub score: u32,
+    pub rng: GameRng,
+}
+
+impl SaveGame {
+    pub fn load(file: &str) -> Result<Self, SaveError> {
+        let value = storage::load::<serde_json::Value>(file)?.ok_or(SaveError::Missing)?;
+
+        Self::from_json(value)
+    }
+
+    pub fn save(&self, file: &str) -> Result<(), SaveError> {
+        Ok(storage::save(file, self)?)
+    }
+
+    /// Checks the version of the save before parsing it, so that older saves fail with a meaningful error.
+    pub fn from_json(value: serde_json::Value) -> Result<Self, SaveError> {
+        let version = value
+            .get("version")
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or_default();
+
+        if version != u64::from(SAVE_VERSION) {
+            return Err(SaveError::Version(version));
+        }
+
+        serde_json::from_value(value).map_err(SaveError::Invalid)
+    }
+}
+
+/// Writes the game in progress into the given file of the storage.
+#[derive(Debug, Clone)]
+pub struct SaveRequest(pub String);
+
+/// Replaces the game in progress with the one saved in the given file of the storage.
+#[derive(Debug, Clone)]
+pub struct PendingLoad(pub String);
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SaveRequest>().add_system_set(
+            ConditionSet::new()
+                .run_if_resource_exists::<AppleSpawner>()
+                .run_if_resource_exists::<Score>()
+                .with_system(save_game.run_if_resource_exists::<ActiveLevel>())
+                .with_system(load_game.run_if_resource_exists::<PendingLoad>())
+                .into(),
+        );
+    }
+}
+
+/// Game in progress, as written into a [`SaveGame`].
+#[derive(SystemParam)]
+pub struct GameInProgress<'w, 's> {
+    active_level: Res<'w, ActiveLevel>,
+    bushes: Query<'w, 's, (&'static Bush, &'static Position)>,
+    stumps: Query<'w, 's, (&'static Stump, &'static Position)>,
+    snakes: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static Snake,
+            &'static MoveTimer,
+            &'static BushCutter,
+            &'static NumberGenerator<u16>,
+        ),
+    >,
+    fragments: Query<
+        'w,
+        's,
+        (
+            &'static SnakeFragment,
+            &'static Position,
+            &'static Direction,
+            Option<&'static PreviousValue<Direction>>,
+        ),
+    >,
+    effects: ActiveEffects<'w, 's>,
+    apples: Query<
+        'w,
+        's,
+        (
+            &'static Position,
+            &'static Transform,
+            Option<&'static GoldenApple>,
+            Option<&'static Lifetime>,
+        ),
+        With<Apple>,
+    >,
+    apple_spawner: Res<'w, AppleSpawner>,
+    apple_sequence: Option<Res<'w, AppleSequence>>,
+    power_ups: Query<'w, 's, (&'static PowerUpItem, &'static Position, &'static Lifetime)>,
+    power_up_spawner: Option<Res<'w, PowerUpSpawner>>,
+    wind_timer: Option<Res<'w, WindTimer>>,
+    mode: Res<'w, PlayMode>,
+    mode_clock: Option<Res<'w, ModeClock>>,
+    score: Res<'w, Score>,
+    rng: Res<'w, GameRng>,
+}
+
+impl GameInProgress<'_, '_> {
+    pub fn capture(&self) -> SaveGame {
+        SaveGame {
+            version: SAVE_VERSION,
+            level: self.capture_level(),
+            stumps: self
+                .stumps
+                .iter()
+                .map(|(stump, position)| StumpState {
+                    position: position.to_array(),
+                    angle: stump.angle(),
+                    regrow: stump.regrow_timer().map(TimerState::capture),
+                })
+                .collect(),
+            snakes: self.capture_snakes(),
+            apples: self.capture_apples(),
+            apple_spawner: AppleSpawnerState {
+                timer: TimerState::capture(&self.apple_spawner.timer),
+                max_apples: self.apple_spawner.max_apples,
+                apple_lifetime: self
+                    .apple_spawner
+                    .apple_lifetime
+                    .as_ref()
+                    .map(|lifetime| lifetime.duration().as_secs_f32()),
+                golden_apple_chance: self.apple_spawner.golden_apple_chance,
+            },
+            apple_sequence: self.apple_sequence.as_deref().cloned(),
+            power_ups: self
+                .power_ups
+                .iter()
+                .map(|(item, position, lifetime)| PowerUpState {
+                    power_up: item.0,
+                    position: position.to_array(),
+                    lifetime: TimerState::capture(lifetime),
+                })
+                .collect(),
+            power_up_spawner: self.power_up_spawner.as_ref().map(|power_up_spawner| PowerUpSpawnerState {
+                timer: TimerState::capture(&power_up_spawner.timer),
+                max_power_ups: power_up_spawner.max_power_ups,
+                power_up_lifetime: power_up_spawner.power_up_lifetime.duration().as_secs_f32(),
+            }),
+            wind_timer: self.wind_timer.as_ref().map(|wind_timer| TimerState::capture(wind_timer)),
+            mode: *self.mode,
+            mode_clock: self.mode_clock.as_ref().map(|mode_clock| TimerState::capture(mode_clock)),
+            score: self.score.0,
+            rng: self.rng.clone(),
+        }
+    }
+
+    /// Returns the level with the bushes still standing.
+    fn capture_level(&self) -> Level {
+        let mut level = self.active_level.0.clone();
+
+        level.tiles = self
+            .bushes
+            .iter()
+            .filter_map(|(bush, position)| {
+                Some(LevelTile {
+                    entity: TileEntity::Bush,
+                    x: u32::try_from(position.x).ok()?,
+                    y: u32::try_from(position.y).ok()?,
+                    angle: Some(bush.angle),
+                })
+            })
+            .collect();
+
+        level
+    }
+
+    fn capture_snakes(&self) -> Vec<SnakeState> {
+        self.snakes
+            .iter()
+            .map(|(entity, snake, move_timer, bush_cutter, fragment_generator)| SnakeState {
+                fragments: self
+                    .fragments
+                    .iter_many(&snake.fragments)
+                    .map(|(fragment, position, direction, previous_direction)| FragmentState {
+                        fragment: *fragment,
+                        position: position.to_array(),
+                        direction: *direction,
+                        previous_direction: previous_direction.map(|previous_direction| previous_direction.0),
+                    })
+                    .collect(),
+                next_direction: snake.next_direction,
+                last_known_tail: snake
+                    .last_known_tail
+                    .map(|(position, direction)| (position.to_array(), direction)),
+                move_timer: TimerState::capture(move_timer),
+                bush_cutter_charges: bush_cutter.charges,
+                fragment_generator: fragment_generator.clone(),
+                effects: self.effects.get(entity).iter().map(EffectState::capture).collect(),
+            })
+            .collect()
+    }
+
+    fn capture_apples(&self) -> Vec<AppleState> {
+        self.apples
+            .iter()
+            .map(|(position, transform, golden_apple, lifetime)| {
+                let (axis, angle) = transform.rotation.to_axis_angle();
+
+                AppleState {
+                    position: position.to_array(),
+                    angle: (angle * axis.z).to_degrees(),
+                    is_golden: golden_apple.is_some(),
+                    lifetime: lifetime.map(|lifetime| TimerState::capture(lifetime)),
+                }
+            })
+            .collect()
+    }
+}
+
+impl SaveGame {
+    /// Spawns the saved game, the entities of the game in progress being already despawned.
+    fn apply(self, commands: &mut Commands, textures: &TextureAssets, apple_spawner: &mut AppleSpawner, timestamp: f64) {
+        let tile_map = level::spawn_level(commands, textures, &self.level);
+
+        level::insert_parameters(commands, &self.level);
+
+        if let (Some(wind), Some(wind_timer)) = (&self.level.wind, self.wind_timer) {
+            let mut timer = WindTimer::new(wind.interval.clone());
+            *timer = wind_timer.restore(false);
+
+            commands.insert_resource(timer);
+        }
+
+        for stump in &self.stumps {
+            bush::spawn_stump(
+                commands,
+                textures,
+                IVec3::from_array(stump.position),
+                stump.angle,
+                stump.regrow.map(|regrow| regrow.restore(false)),
+            );
+        }
+
+        for snake in &self.snakes {
+            snake.spawn(commands, timestamp);
+        }
+
+        for apple in &self.apples {
+            apple.spawn(commands, textures);
+        }
+
+        for item in &self.power_ups {
+            power_up::spawn_power_up_item(
+                commands,
+                item.power_up,
+                IVec3::from_array(item.position),
+                item.lifetime.restore_lifetime(),
+            );
+        }
+
+        match self.power_up_spawner {
+            Some(power_up_spawner) => commands.insert_resource(PowerUpSpawner {
+                timer: power_up_spawner.timer.restore(true),
+                max_power_ups: power_up_spawner.max_power_ups,
+                power_up_lifetime: Lifetime::from_seconds(power_up_spawner.power_up_lifetime),
+            }),
+            None => commands.remove_resource::<PowerUpSpawner>(),
+        }
+
+        apple_spawner.timer = self.apple_spawner.timer.restore(true);
+        apple_spawner.max_apples = self.apple_spawner.max_apples;
+        apple_spawner.apple_lifetime = self.apple_spawner.apple_lifetime.map(Lifetime::from_seconds);
+        apple_spawner.golden_apple_chance = self.apple_spawner.golden_apple_chance;
+
+        match self.apple_sequence {
+            Some(apple_sequence) => commands.insert_resource(apple_sequence),
+            None => commands.remove_resource::<AppleSequence>(),
+        }
+
+        match self.mode_clock {
+            Some(mode_clock) => commands.insert_resource(ModeClock(mode_clock.restore(false))),
+            None => commands.remove_resource::<ModeClock>(),
+        }
+
+        commands.insert_resource(tile_map);
+        commands.insert_resource(ActiveLevel(self.level));
+        commands.insert_resource(self.mode);
+        commands.insert_resource(Score(self.score));
+        commands.insert_resource(self.rng);
+        // snapshots refer to the entities that were just despawned
+        commands.insert_resource(RewindBuffer::default());
+    }
+}
+
+impl SnakeState {
+    fn spawn(&self, commands: &mut Commands, timestamp: f64) {
+        let fragments = self
+            .fragments
+            .iter()
+            .map(|fragment| {
+                let entity = snake::spawn_snake_fragment(
+                    commands,
+                    IVec3::from_array(fragment.position),
+                    fragment.direction,
+                    fragment.fragment,
+                );
+
+                // the value tracker records the saved direction on top of the previous one
+                if let Some(previous_direction) = fragment.previous_direction {
+                    let mut history = ValueHistory::new(DEFAULT_HISTORY_DEPTH);
+                    history.push(timestamp, previous_direction);
+
+                    commands.entity(entity).insert(history);
+                }
+
+                entity
+            })
+            .collect();
+
+        let entity = commands
+            .spawn()
+            .insert_bundle((
+                Snake {
+                    fragments,
+                    next_direction: self.next_direction,
+                    last_known_tail: self
+                        .last_known_tail
+                        .map(|(position, direction)| (Position(IVec3::from_array(position)), direction)),
+                },
+                self.fragment_generator.clone(),
+                BushCutter {
+                    charges: self.bush_cutter_charges,
+                },
+                MoveTimer(self.move_timer.restore(true)),
+                Swallow::default(),
+                Name::new("Snake"),
+            ))
+            .id();
+
+        for effect in &self.effects {
+            effect
+                .power_up
+                .insert_effect(commands, entity, effect.level, effect.lifetime.restore_lifetime());
+        }
+    }
+}
+
+impl AppleState {
+    fn spawn(&self, commands: &mut Commands, textures: &TextureAssets) {
+        let mut apple_builder = AppleBuilder::default()
+            .with_position(IVec3::from_array(self.position))
+            .with_angle(self.angle);
+
+        if self.is_golden {
+            apple_builder = apple_builder.golden();
+        }
+
+        let entity = apple_builder.spawn(commands, textures);
+
+        if let Some(lifetime) = self.lifetime {
+            commands.entity(entity).insert(lifetime.restore_lifetime());
+        }
+    }
+}
+
+fn save_game(mut save_reader: EventReader<SaveRequest>, game: GameInProgress) {
+    for SaveRequest(file) in save_reader.iter() {
+        match game.capture().save(file) {
+            Ok(()) => info!("game saved to {file}"),
+            Err(e) => error!("cannot save game: {e}"),
+        }
+    }
+}
+
+fn load_game(
+    mut commands: Commands,
+    pending_load: Res<PendingLoad>,
+    mut apple_spawner: ResMut<AppleSpawner>,
+    textures: Res<TextureAssets>,
+    time: Res<Time>,
+    status: Res<CurrentState<GameStatus>>,
+    game_query: Query<
+        Entity,
+        Or<(
+            With<LevelEntity>,
+            With<Apple>,
+            With<PowerUpItem>,
+            With<Snake>,
+            With<SnakeFragment>,
+            With<Wind>,
+        )>,
+    >,
+) {
+    commands.remove_resource::<PendingLoad>();
+
+    let save = match SaveGame::load(&pending_load.0) {
+        Ok(save) => save,
+        Err(e) => {
+            error!("cannot load game: {e}");
+
+            return;
+        }
+    };
+
+    game_query.for_each(|entity| commands.entity(entity).despawn_recursive());
+
+    save.apply(
+        &mut commands,
+        &textures,
+        &mut apple_spawner,
+        time.seconds_since_startup(),
+    );
+
+    // the loaded game goes on, even if the one it replaces was over
+    if status.0 == GameStatus::GameOver {
+        commands.insert_resource(NextState(GameStatus::Running));
+    }
+
+    info!("game loaded from {}", pending_load.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::CommandQueue;
+    use bevy::prelude::*;
+    use serde_json::json;
+
+    use super::{
+        AppleSpawnerState, AppleState, EffectState, FragmentState, GameInProgress, PowerUpSpawnerState, PowerUpState,
+        SaveError, SaveGame, SnakeState, StumpState, TimerState, SAVE_VERSION,
+    };
+    use crate::assets::TextureAssets;
+    use crate::game::apple::AppleSpawner;
+    use crate::game::components::{Direction, NumberGenerator};
+    use crate::game::level::{Level, LevelTile, TileEntity};
+    use crate::game::play_mode::PlayMode;
+    use crate::game::power_up::PowerUp;
+    use crate::game::rng::GameRng;
+    use crate::game::snake::SnakeFragment;
+
+    /// Timers are given durations and elapsed times that floats represent exactly, to compare them as they are.
+    const fn timer(duration: f32, elapsed: f32) -> TimerState {
+        TimerState { duration, elapsed }
+    }
+
+    fn saved_game() -> SaveGame {
+        let level = Level {
+            tiles: vec![LevelTile {
+                entity: TileEntity::Bush,
+                x: 3,
+                y: 4,
+                angle: Some(30.0),
+            }],
+            ..Level::default()
+        };
+
+        let fragments = [
+            (SnakeFragment::Head, [6, 5, 1]),
+            (SnakeFragment::Body(1), [5, 5, 1]),
+            (SnakeFragment::Tail, [4, 5, 1]),
+        ];
+
+        SaveGame {
+            version: SAVE_VERSION,
+            level,
+            stumps: vec![StumpState {
+                position: [2, 2, 1],
+                angle: 45.0,
+                regrow: Some(timer(10.0, 2.5)),
+            }],
+            snakes: vec![SnakeState {
+                fragments: fragments
+                    .into_iter()
+                    .map(|(fragment, position)| FragmentState {
+                        fragment,
+                        position,
+                        direction: Direction::Right,
+                        previous_direction: None,
+                    })
+                    .collect(),
+                next_direction: Some(Direction::Up),
+                last_known_tail: None,
+                move_timer: timer(0.25, 0.125),
+                bush_cutter_charges: 1,
+                fragment_generator: NumberGenerator::from_range(0..3).with_seed(7),
+                effects: vec![EffectState {
+                    power_up: PowerUp::Shield,
+                    level: 1,
+                    lifetime: timer(8.0, 2.5),
+                }],
+            }],
+            apples: vec![AppleState {
+                position: [8, 2, 1],
+                angle: 0.0,
+                is_golden: true,
+                lifetime: Some(timer(5.0, 1.5)),
+            }],
+            apple_spawner: AppleSpawnerState {
+                timer: timer(2.0, 0.5),
+                max_apples: 3,
+                apple_lifetime: Some(5.0),
+                golden_apple_chance: 0.1,
+            },
+            apple_sequence: None,
+            power_ups: vec![PowerUpState {
+                power_up: PowerUp::Magnet,
+                position: [1, 7, 1],
+                lifetime: timer(6.0, 0.5),
+            }],
+            power_up_spawner: Some(PowerUpSpawnerState {
+                timer: timer(12.0, 4.0),
+                max_power_ups: 1,
+                power_up_lifetime: 6.0,
+            }),
+            wind_timer: Some(timer(8.0, 2.0)),
+            mode: PlayMode::Endless,
+            mode_clock: None,
+            score: 42,
+            rng: GameRng::from_seed(7),
+        }
+    }
+
+    #[test]
+    fn it_rejects_saves_of_other_versions() {
+        let result = SaveGame::from_json(json!({ "version": 0, "score": 3 }));
+
+        assert!(matches!(result, Err(SaveError::Version(0))));
+    }
+
+    #[test]
+    fn it_reports_corrupted_saves() {
+        let result = SaveGame::from_json(json!({ "version": super::SAVE_VERSION, "score": "three" }));
+
+        assert!(matches!(result, Err(SaveError::Invalid(_))));
+    }
+
+    #[test]
+    fn it_restores_the_saved_game() {
+        let json = serde_json::to_value(saved_game()).unwrap();
+        let save = SaveGame::from_json(json.clone()).unwrap();
+
+        let mut world = World::new();
+        let mut queue = CommandQueue::default();
+        let mut apple_spawner = AppleSpawner {
+            timer: Timer::from_seconds(1.0, true),
+            max_apples: 1,
+            apple_lifetime: None,
+            golden_apple_chance: 0.0,
+        };
+        let textures = TextureAssets {
+            apple: Handle::default(),
+            apple_leaf: Handle::default(),
+            bush_lower: Handle::default(),
+            bush_upper: Handle::default(),
+        };
+
+        save.apply(&mut Commands::new(&mut queue, &world), &textures, &mut apple_spawner, 0.0);
+        queue.apply(&mut world);
+        world.insert_resource(apple_spawner);
+
+        let mut capture = IntoSystem::into_system(|game: GameInProgress| game.capture());
+        capture.initialize(&mut world);
+
+        assert_eq!(serde_json::to_value(capture.run((), &mut world)).unwrap(), json);
+    }
+}
@@ -0,0 +1,5 @@
+use derive_more::{Deref, DerefMut};
+
+/// Points earned during the current game, one per apple eaten unless a score multiplier is active.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deref, DerefMut)]
+pub struct Score(pub u32);
@@ -1,17 +1,28 @@
 use std::mem;
 use std::num::NonZeroUsize;
 
+use bevy::ecs::system::SystemParam;
+use bevy::math::Vec3Swizzles;
 use bevy::prelude::*;
 use derive_more::{Deref, DerefMut};
+use iyes_loopless::prelude::*;
 use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::assets::TextureAssets;
+use crate::assets::SnakeAtlas;
+use crate::audio::SoundEffect;
 use crate::systems;
 
-use super::apple::Apple;
+use super::animation::Swallow;
+use super::apple::{Apple, GoldenApple};
+use super::bush::{Bush, BushCutter};
 use super::components::{Direction, NumberGenerator, Position, RigidBody};
+use super::particles::{ParticleBurst, ParticleConfig};
+use super::power_up::{Ghost, PowerUpItem, ScoreMultiplier, Shield, TimedEffect};
 use super::queries::spatial::DirectionQuery;
 use super::queries::SpatialQuery;
+use super::score::Score;
+use super::value_tracker::PreviousValue;
 use super::{GameStage, GameSystem};
 
 #[derive(Debug, Component, Reflect)]
@@ -23,11 +34,22 @@ pub struct Snake {
 }
 
 #[derive(Debug, Component, Deref, DerefMut)]
-pub struct MoveTimer(Timer);
+pub struct MoveTimer(pub Timer);
+
+/// Marks a snake which ran into something it could not go through, and stopped moving.
+#[derive(Debug, Default, Component)]
+pub struct Dead;
+
+/// Keeps a snake alive whatever it runs into, letting it go through.
+#[derive(Debug, Default, Component)]
+pub struct Immortal;
 
 #[derive(Debug)]
 pub enum SnakeEvent {
-    HeadCollide(Entity),
+    /// The head of the snake moved onto the tile of another rigid body.
+    HeadCollide { snake: Entity, entity: Entity },
+    /// The snake ate an apple, and grew a new tail.
+    Grow { snake: Entity, tail: Entity },
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Actionlike)]
@@ -38,8 +60,20 @@ pub enum SnakeAction {
     MoveRight,
 }
 
-#[derive(Debug, Copy, Clone, Component, Reflect)]
+impl SnakeAction {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::MoveUp => "Up",
+            Self::MoveDown => "Down",
+            Self::MoveLeft => "Left",
+            Self::MoveRight => "Right",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Component, Reflect, Serialize, Deserialize)]
 #[reflect_value()]
+#[serde(rename_all = "kebab-case")]
 pub enum SnakeFragment {
     Head,
     Body(u16),
@@ -67,19 +101,25 @@ impl Plugin for SnakePlugin {
                 SystemSet::new()
                     .after(GameSystem::CheckPosition)
                     .label(GameSystem::CollisionDetection)
-                    .with_system(grow_snake), // .with_system(check_snake_head_collision),
+                    .with_system(grow_snake)
+                    .with_system(check_snake_head_collision),
+            )
+            .add_system(
+                kill_snakes
+                    .label(GameSystem::Death)
+                    .after(GameSystem::CollisionDetection),
             )
             .add_system_set_to_stage(
                 GameStage::Transform,
                 SystemSet::new()
-                    .with_system(update_snake_sprite)
+                    .with_system(update_snake_sprite.run_if_resource_exists::<SnakeAtlas>())
                     .with_system(update_snake_transform),
             );
     }
 }
 
 fn turn_snake_head(
-    mut snake_query: Query<(&ActionState<SnakeAction>, &mut Snake)>,
+    mut snake_query: Query<(&ActionState<SnakeAction>, &mut Snake), Without<Dead>>,
     mut direction_query: Query<&Direction>,
 ) {
     snake_query.for_each_mut(|(action, mut snake)| {
@@ -103,7 +143,11 @@ fn turn_snake_head(
     });
 }
 
-fn move_snake(mut snake_query: Query<(&mut Snake, &MoveTimer)>, mut spatial_query: Query<SpatialQuery>) {
+fn move_snake(
+    mut snake_query: Query<(&mut Snake, &MoveTimer), Without<Dead>>,
+    mut spatial_query: Query<SpatialQuery>,
+    mut sound_writer: EventWriter<SoundEffect>,
+) {
     snake_query.for_each_mut(|(mut snake, timer)| {
         if timer.just_finished() {
             // save the tail position and direction to ease snake growth later on
@@ -112,7 +156,13 @@ fn move_snake(mut snake_query: Query<(&mut Snake, &MoveTimer)>, mut spatial_quer
 
             let mut snake_head = spatial_query.get_mut(snake.fragments[0]).unwrap();
 
-            *snake_head.direction.current = snake.next_direction.take().unwrap_or(*snake_head.direction.current);
+            if let Some(next_direction) = snake.next_direction.take() {
+                if next_direction != *snake_head.direction.current {
+                    sound_writer.send(SoundEffect::Turn);
+                }
+
+                *snake_head.direction.current = next_direction;
+            }
 
             let (mut position, mut direction) = (
                 *snake_head.position + snake_head.direction.current.to_ivec3(),
@@ -129,37 +179,48 @@ fn move_snake(mut snake_query: Query<(&mut Snake, &MoveTimer)>, mut spatial_quer
     });
 }
 
+/// Picks the atlas sprite of the fragments whose direction changed, or which just appeared.
 pub fn update_snake_sprite(
-    snake_query: Query<&Snake>,
-    mut query: Query<(&mut Handle<Image>, &SnakeFragment, DirectionQuery)>,
-    textures: Res<TextureAssets>,
+    mut query: Query<
+        (
+            &mut TextureAtlasSprite,
+            &mut Handle<TextureAtlas>,
+            &SnakeFragment,
+            DirectionQuery,
+        ),
+        Or<(
+            Added<TextureAtlasSprite>,
+            Changed<SnakeFragment>,
+            Changed<Direction>,
+            Changed<PreviousValue<Direction>>,
+        )>,
+    >,
+    snake_atlas: Res<SnakeAtlas>,
 ) {
-    snake_query.for_each(|snake| {
-        let mut iter = query.iter_many_mut(snake.fragments.iter().copied());
-
-        while let Some((mut texture, snake_fragment, direction)) = iter.fetch_next() {
-            let new_texture = match snake_fragment {
-                SnakeFragment::Head => textures.snake_head.clone(),
-                SnakeFragment::Tail => textures.snake_tail.clone(),
-                SnakeFragment::Body(seed) => {
-                    let fragment_textures = &textures.snake_fragment_assets[*seed as usize];
-
-                    match (direction.current, direction.previous) {
-                        (direction, Some(previous_direction)) if direction.clockwise() == **previous_direction => {
-                            fragment_textures.right_curved.clone()
-                        }
-                        (direction, Some(previous_direction))
-                            if direction.counter_clockwise() == **previous_direction =>
-                        {
-                            fragment_textures.left_curved.clone()
-                        }
-                        _ => fragment_textures.straight.clone(),
+    query.for_each_mut(|(mut sprite, mut atlas, snake_fragment, direction)| {
+        let index = match snake_fragment {
+            SnakeFragment::Head => snake_atlas.head,
+            SnakeFragment::Tail => snake_atlas.tail,
+            SnakeFragment::Body(seed) => {
+                let indices = &snake_atlas.fragments[*seed as usize % snake_atlas.fragments.len()];
+
+                match (direction.current, direction.previous) {
+                    (direction, Some(previous_direction)) if direction.clockwise() == **previous_direction => {
+                        indices.right_curved
                     }
+                    (direction, Some(previous_direction)) if direction.counter_clockwise() == **previous_direction => {
+                        indices.left_curved
+                    }
+                    _ => indices.straight,
                 }
-            };
+            }
+        };
 
-            *texture = new_texture;
+        if *atlas != snake_atlas.atlas {
+            *atlas = snake_atlas.atlas.clone();
         }
+
+        sprite.index = index;
     });
 }
 
@@ -184,20 +245,51 @@ pub fn update_snake_transform(
     });
 }
 
+/// The feedback given when a snake eats an apple.
+#[derive(SystemParam)]
+struct MealWriters<'w, 's> {
+    bursts: EventWriter<'w, 's, ParticleBurst>,
+    sounds: EventWriter<'w, 's, SoundEffect>,
+    snake_events: EventWriter<'w, 's, SnakeEvent>,
+}
+
 fn grow_snake(
     mut commands: Commands,
-    mut snake_query: Query<(&mut Snake, &mut NumberGenerator<u16>)>,
+    mut snake_query: Query<(
+        Entity,
+        &mut Snake,
+        &mut NumberGenerator<u16>,
+        &mut BushCutter,
+        Option<&TimedEffect<ScoreMultiplier>>,
+    )>,
     mut snake_fragment_query: Query<&mut SnakeFragment>,
+    mut meal_writers: MealWriters,
+    mut score: Option<ResMut<Score>>,
     snake_position_query: Query<&Position, Without<Apple>>,
-    apple_position_query: Query<(Entity, &Position), With<Apple>>,
+    apple_position_query: Query<(Entity, &Position, &Transform, Option<&GoldenApple>), With<Apple>>,
 ) {
-    snake_query.for_each_mut(|(mut snake, mut number_generator)| {
+    snake_query.for_each_mut(|(snake_entity, mut snake, mut number_generator, mut bush_cutter, score_multiplier)| {
         let snake_head_position = snake_position_query.get(snake.fragments[0]).unwrap();
 
-        for (apple_entity, apple_position) in apple_position_query.iter() {
+        for (apple_entity, apple_position, apple_transform, golden_apple) in apple_position_query.iter() {
             if *snake_head_position == *apple_position {
                 commands.entity(apple_entity).despawn_recursive();
 
+                if let Some(score) = score.as_mut() {
+                    score.0 += score_multiplier.map_or(1, TimedEffect::multiplier);
+                }
+
+                if golden_apple.is_some() {
+                    bush_cutter.charges += 1;
+                }
+
+                meal_writers.bursts.send(ParticleBurst {
+                    config: ParticleConfig::apple_burst(),
+                    translation: apple_transform.translation.truncate(),
+                    count: 12,
+                });
+                meal_writers.sounds.send(SoundEffect::Eat);
+
                 let mut snake_tail_fragment = snake_fragment_query.get_mut(*snake.fragments.last().unwrap()).unwrap();
                 *snake_tail_fragment = SnakeFragment::Body(number_generator.generate());
 
@@ -205,12 +297,93 @@ fn grow_snake(
                 let snake_tail_entity = spawn_snake_fragment(&mut commands, position.0, direction, SnakeFragment::Tail);
 
                 snake.fragments.push(snake_tail_entity);
+                meal_writers.snake_events.send(SnakeEvent::Grow {
+                    snake: snake_entity,
+                    tail: snake_tail_entity,
+                });
             }
         }
     });
 }
 
-// fn check_snake_head_collision(snake_query: Query<>)
+fn check_snake_head_collision(
+    mut snake_writer: EventWriter<SnakeEvent>,
+    mut sound_writer: EventWriter<SoundEffect>,
+    snake_query: Query<(Entity, &Snake, &MoveTimer), Without<Dead>>,
+    rigid_body_query: Query<(Entity, &Position), (With<RigidBody>, Without<Apple>, Without<PowerUpItem>)>,
+) {
+    snake_query.for_each(|(snake_entity, snake, timer)| {
+        if !timer.just_finished() {
+            return;
+        }
+
+        let head = snake.fragments[0];
+        let head_position = match rigid_body_query.get(head) {
+            Ok((_, position)) => position.xy(),
+            Err(_) => return,
+        };
+
+        rigid_body_query.for_each(|(entity, position)| {
+            if entity != head && position.xy() == head_position {
+                snake_writer.send(SnakeEvent::HeadCollide {
+                    snake: snake_entity,
+                    entity,
+                });
+                sound_writer.send(SoundEffect::Collision);
+            }
+        });
+    });
+}
+
+/// The power-ups which can save a snake from a collision.
+#[derive(SystemParam)]
+struct Protections<'w, 's> {
+    ghosts: Query<'w, 's, &'static Snake, With<TimedEffect<Ghost>>>,
+    shields: Query<'w, 's, (), With<TimedEffect<Shield>>>,
+}
+
+/// Kills the snakes whose head ran into something, unless it is a bush they can cut through, they are immortal, they
+/// are a ghost going through it or a shield absorbs the collision.
+fn kill_snakes(
+    mut commands: Commands,
+    mut snake_reader: EventReader<SnakeEvent>,
+    mut shielded: Local<Vec<Entity>>,
+    cutter_query: Query<&BushCutter>,
+    bush_query: Query<(), With<Bush>>,
+    immortal_query: Query<(), With<Immortal>>,
+    protections: Protections,
+) {
+    // a shield absorbs every collision of the move it breaks on
+    shielded.clear();
+
+    for event in snake_reader.iter() {
+        let (snake, entity) = match event {
+            SnakeEvent::HeadCollide { snake, entity } => (snake, entity),
+            SnakeEvent::Grow { .. } => continue,
+        };
+
+        let can_cut = bush_query.contains(*entity)
+            && cutter_query
+                .get(*snake)
+                .is_ok_and(|bush_cutter| bush_cutter.charges > 0);
+
+        let can_go_through = protections
+            .ghosts
+            .get(*snake)
+            .is_ok_and(|ghost| bush_query.contains(*entity) || ghost.fragments.contains(entity));
+
+        if can_cut || can_go_through || immortal_query.contains(*snake) || shielded.contains(snake) {
+            continue;
+        }
+
+        if protections.shields.contains(*snake) {
+            commands.entity(*snake).remove::<TimedEffect<Shield>>();
+            shielded.push(*snake);
+        } else {
+            commands.entity(*snake).insert(Dead);
+        }
+    }
+}
 
 // fn stop_snake(mut commands: Commands, mut collision_reader: EventReader<DiscreteCollisionEvent<Snake,
 // SnakeFragment>>) {     for collision in collision_reader.iter() {
@@ -254,20 +427,22 @@ pub fn spawn_snake(
                 last_known_tail: None,
             },
             number_generator,
+            BushCutter::default(),
             MoveTimer(Timer::from_seconds(0.125, true)),
+            Swallow::default(),
             Name::new("Snake"),
         ))
         .id()
 }
 
-fn spawn_snake_fragment(
+pub fn spawn_snake_fragment(
     commands: &mut Commands,
     position: IVec3,
     direction: Direction,
     fragment: SnakeFragment,
 ) -> Entity {
     commands
-        .spawn_bundle(SpriteBundle::default())
+        .spawn_bundle(SpriteSheetBundle::default())
         .insert_bundle((
             direction,
             fragment,
@@ -5,9 +5,8 @@ use bevy::math::Vec3Swizzles;
 use bevy::prelude::*;
 use itertools::Itertools;
 use iyes_loopless::prelude::*;
-// use serde::{Deserialize, Serialize};
 
-use super::components::{Position, RigidBody};
+use super::components::{Detached, Position, RigidBody, Unbounded};
 use super::{GameStage, GameSystem};
 
 pub const TILE_SIZE: f32 = 128.;
@@ -17,31 +16,47 @@ pub enum TileMapPosition {
     Offset(Vec2),
 }
 
-// #[derive(Debug, Serialize, Deserialize)]
-// #[serde(rename_all = "kebab-case")]
-// pub enum TileEntity {
-//     Bush,
-// }
-
-// #[derive(Debug, Serialize, Deserialize)]
-// pub struct TileBuilder {
-//     pub x: u32,
-//     pub y: u32,
-//     pub entity: TileEntity,
-// }
-
-// #[derive(Debug, Serialize, Deserialize)]
-// pub struct TileMapBuilder {
-//     pub width: usize,
-//     pub height: usize,
-//     pub tiles: Vec<TileBuilder>
-// }
-
 pub struct TileMapOptions {
     pub tile_size: f32,
     pub position: TileMapPosition,
 }
 
+impl TileMapOptions {
+    /// Returns the world position of the center of the tile (0, 0).
+    pub fn offset(&self, tile_map: &TileMap) -> Vec2 {
+        match self.position {
+            TileMapPosition::Centered => {
+                let half_tile_size = self.tile_size / 2.0;
+
+                Vec2::new(
+                    -(tile_map.width() as f32 * self.tile_size / 2.0) + half_tile_size,
+                    -(tile_map.height() as f32 * self.tile_size / 2.0) + half_tile_size,
+                )
+            }
+            TileMapPosition::Offset(offset) => offset,
+        }
+    }
+
+    pub fn tile_to_world(&self, tile_map: &TileMap, position: IVec2) -> Vec2 {
+        position.as_vec2() * self.tile_size + self.offset(tile_map)
+    }
+
+    /// Returns the position of the tile under the given world position, if any.
+    pub fn world_to_tile(&self, tile_map: &TileMap, world_position: Vec2) -> Option<UVec2> {
+        let position = ((world_position - self.offset(tile_map)) / self.tile_size).round();
+
+        if position.x < 0.0
+            || position.y < 0.0
+            || position.x >= tile_map.width() as f32
+            || position.y >= tile_map.height() as f32
+        {
+            return None;
+        }
+
+        Some(position.as_uvec2())
+    }
+}
+
 pub struct Tile {
     entity: Option<Entity>,
     position: UVec2,
@@ -105,6 +120,11 @@ impl TileMap {
         self.tiles.get(i)
     }
 
+    /// Returns the tile under the given position, unless the position is off the map.
+    pub fn tile_under(&self, position: IVec2) -> Option<&Tile> {
+        self.tile_at(u32::try_from(position.x).ok()?, u32::try_from(position.y).ok()?)
+    }
+
     pub fn tile_mut_at(&mut self, x: u32, y: u32) -> Option<&mut Tile> {
         let i = self.position_to_index(x, y);
 
@@ -155,7 +175,10 @@ fn clean_tiles(entities: RemovedComponents<RigidBody>, mut tile_map: ResMut<Tile
             .remove(&entity)
             .and_then(|position| tile_map.tile_mut_at(position.x, position.y))
         {
-            tile.entity = None;
+            // another entity may have moved onto the tile in the meantime
+            if tile.entity == Some(entity) {
+                tile.entity = None;
+            }
         }
     });
 }
@@ -195,7 +218,10 @@ fn update_tiles(
     });
 }
 
-pub fn clamp_position(mut query: Query<&mut Position, Changed<Position>>, tile_map: Res<TileMap>) {
+pub fn clamp_position(
+    mut query: Query<&mut Position, (Changed<Position>, Without<Unbounded>)>,
+    tile_map: Res<TileMap>,
+) {
     query.for_each_mut(|mut position| {
         let width = tile_map.width() as i32;
         let height = tile_map.height() as i32;
@@ -214,33 +240,24 @@ pub fn clamp_position(mut query: Query<&mut Position, Changed<Position>>, tile_m
 }
 
 pub fn position_to_world(
-    mut query: Query<(&mut Transform, &Position)>,
+    mut query: Query<(&mut Transform, &Position), Without<Detached>>,
     tile_map: Res<TileMap>,
     tile_map_options: Res<TileMapOptions>,
 ) {
     query.for_each_mut(|(mut transform, position)| {
-        let offset = match tile_map_options.position {
-            TileMapPosition::Centered => {
-                let half_tile_size = tile_map_options.tile_size / 2.0;
-
-                Vec2::new(
-                    -(tile_map.width() as f32 * tile_map_options.tile_size / 2.0) + half_tile_size,
-                    -(tile_map.height() as f32 * tile_map_options.tile_size / 2.0) + half_tile_size,
-                )
-            }
-            TileMapPosition::Offset(offset) => offset,
-        };
-
-        transform.translation = Vec3::new(
-            position.0.x as f32 * tile_map_options.tile_size + offset.x,
-            position.0.y as f32 * tile_map_options.tile_size + offset.y,
-            position.0.z as f32,
-        );
+        transform.translation = tile_map_options
+            .tile_to_world(&tile_map, position.0.xy())
+            .extend(position.0.z as f32);
     });
 }
 
-pub fn size_to_world(mut query: Query<&mut Sprite>, tile_map_options: Res<TileMapOptions>) {
-    query.for_each_mut(|mut sprite| {
-        sprite.custom_size = Some(Vec2::splat(tile_map_options.tile_size));
-    });
+pub fn size_to_world(
+    mut query: Query<&mut Sprite>,
+    mut atlas_query: Query<&mut TextureAtlasSprite>,
+    tile_map_options: Res<TileMapOptions>,
+) {
+    let size = Some(Vec2::splat(tile_map_options.tile_size));
+
+    query.for_each_mut(|mut sprite| sprite.custom_size = size);
+    atlas_query.for_each_mut(|mut sprite| sprite.custom_size = size);
 }
@@ -1,5 +1,4 @@
-use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::VecDeque;
 use std::marker::PhantomData;
 
 use bevy::prelude::*;
@@ -7,45 +6,184 @@ use derive_more::Deref;
 
 use super::GameStage;
 
+/// Number of values remembered per entity unless configured otherwise.
+pub const DEFAULT_HISTORY_DEPTH: usize = 16;
+
 #[derive(Debug, Clone, Component, Deref)]
 pub struct PreviousValue<T: Component + Clone>(pub T);
 
-struct ValueTracker<T>(HashMap<Entity, T>);
+/// Timestamped value of a tracked component.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackedValue<T> {
+    /// Seconds since startup at which the value was observed.
+    pub timestamp: f64,
+    pub value: T,
+}
 
-impl<T> Default for ValueTracker<T> {
-    fn default() -> Self {
-        Self(HashMap::default())
+/// Bounded history of the values taken by a component of the same entity, newest first.
+///
+/// Being a component itself, the history goes away along with its entity.
+#[derive(Debug, Clone, Component)]
+pub struct ValueHistory<T: Component + Clone> {
+    values: VecDeque<TrackedValue<T>>,
+    depth: usize,
+}
+
+impl<T: Component + Clone> ValueHistory<T> {
+    pub fn new(depth: usize) -> Self {
+        let depth = depth.max(1);
+
+        Self {
+            values: VecDeque::with_capacity(depth),
+            depth,
+        }
+    }
+
+    /// Records a new value, forgetting the oldest one when the history is full.
+    pub fn push(&mut self, timestamp: f64, value: T) {
+        if self.values.len() == self.depth {
+            self.values.pop_back();
+        }
+
+        self.values.push_front(TrackedValue { timestamp, value });
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn latest(&self) -> Option<&TrackedValue<T>> {
+        self.values.front()
+    }
+
+    /// Returns the value the component had before its latest change.
+    pub fn previous(&self) -> Option<&TrackedValue<T>> {
+        self.values.get(1)
+    }
+
+    /// Returns the value that was `steps` changes ago, `0` being the latest one.
+    pub fn get(&self, steps: usize) -> Option<&TrackedValue<T>> {
+        self.values.get(steps)
+    }
+
+    /// Returns the value the component had at the given time, if it is still remembered.
+    pub fn at(&self, timestamp: f64) -> Option<&TrackedValue<T>> {
+        self.values.iter().find(|tracked| tracked.timestamp <= timestamp)
+    }
+
+    /// Iterates over the remembered values, newest first.
+    pub fn iter(&self) -> impl Iterator<Item = &TrackedValue<T>> {
+        self.values.iter()
     }
 }
 
-pub struct ValueTrackerPlugin<T>(PhantomData<T>);
+struct ValueTracker<T> {
+    depth: usize,
+    marker: PhantomData<T>,
+}
+
+/// Tracks the changes of the `T` component of every entity into a [`ValueHistory`], along with its
+/// [`PreviousValue`].
+pub struct ValueTrackerPlugin<T> {
+    depth: usize,
+    marker: PhantomData<T>,
+}
 
 impl<T> Default for ValueTrackerPlugin<T> {
     fn default() -> Self {
-        Self(PhantomData)
+        Self::with_depth(DEFAULT_HISTORY_DEPTH)
+    }
+}
+
+impl<T> ValueTrackerPlugin<T> {
+    /// Remembers up to `depth` values per entity, and at least the current and previous ones.
+    pub fn with_depth(depth: usize) -> Self {
+        Self {
+            depth: depth.max(2),
+            marker: PhantomData,
+        }
     }
 }
 
 impl<T: Component + Clone> Plugin for ValueTrackerPlugin<T> {
     fn build(&self, app: &mut App) {
-        app.init_resource::<ValueTracker<T>>()
-            .add_system_to_stage(GameStage::Track, track_entity::<T>);
+        app.insert_resource(ValueTracker::<T> {
+            depth: self.depth,
+            marker: PhantomData,
+        })
+        .add_system_to_stage(GameStage::Track, track_entity::<T>);
     }
 }
 
 fn track_entity<T: Component + Clone>(
     mut commands: Commands,
-    query: Query<(Entity, &T), Changed<T>>,
-    mut value_tracker: ResMut<ValueTracker<T>>,
+    mut query: Query<(Entity, &T, Option<&mut ValueHistory<T>>), Changed<T>>,
+    value_tracker: Res<ValueTracker<T>>,
+    time: Res<Time>,
 ) {
-    query.for_each(|(entity, component)| match value_tracker.0.entry(entity) {
-        Entry::Occupied(mut entry) => {
-            let previous_component = entry.insert(component.clone());
+    let timestamp = time.seconds_since_startup();
 
-            commands.entity(entity).insert(PreviousValue(previous_component));
-        }
-        Entry::Vacant(entry) => {
-            entry.insert(component.clone());
+    query.for_each_mut(|(entity, component, history)| {
+        if let Some(mut history) = history {
+            history.push(timestamp, component.clone());
+
+            if let Some(previous) = history.previous() {
+                commands.entity(entity).insert(PreviousValue(previous.value.clone()));
+            }
+        } else {
+            let mut history = ValueHistory::new(value_tracker.depth);
+            history.push(timestamp, component.clone());
+
+            commands.entity(entity).insert(history);
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::ValueHistory;
+
+    #[derive(Debug, Clone, PartialEq, Component)]
+    struct Value(u32);
+
+    #[test]
+    fn it_forgets_oldest_values() {
+        let mut history = ValueHistory::new(3);
+
+        for i in 0..5 {
+            history.push(f64::from(i), Value(i));
+        }
+
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.latest().map(|tracked| &tracked.value), Some(&Value(4)));
+        assert_eq!(history.previous().map(|tracked| &tracked.value), Some(&Value(3)));
+        assert_eq!(history.iter().last().map(|tracked| &tracked.value), Some(&Value(2)));
+    }
+
+    #[test]
+    fn it_finds_value_at_timestamp() {
+        let mut history = ValueHistory::new(4);
+
+        history.push(1.0, Value(1));
+        history.push(2.0, Value(2));
+        history.push(3.0, Value(3));
+
+        assert_eq!(history.at(2.5).map(|tracked| &tracked.value), Some(&Value(2)));
+        assert_eq!(history.at(3.0).map(|tracked| &tracked.value), Some(&Value(3)));
+        assert!(history.at(0.5).is_none());
+    }
+}
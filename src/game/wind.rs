@@ -1,22 +1,87 @@
 use std::ops::Range;
 use std::time::Duration;
 
+use bevy::math::Vec3Swizzles;
 use bevy::prelude::*;
 use bevy_tweening::lens::TransformScaleLens;
 use bevy_tweening::{Animator, EaseFunction, Sequence, Tween, TweeningType};
 use derive_more::{Deref, DerefMut};
-use iyes_loopless::prelude::IntoConditionalSystem;
+use iyes_loopless::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
+use crate::assets::TextureAssets;
+use crate::audio::SoundEffect;
 use crate::run_criterias;
 
-use super::components::{Direction, NumberGenerator, Position};
+use super::apple::Apple;
+use super::components::{Direction, NumberGenerator, Position, Unbounded};
+use super::particles::{ParticleConfig, ParticleEmitter};
+use super::rng::GameRng;
+use super::snake::Snake;
+use super::tile_map::{Tile, TileMap};
 use super::GameSystem;
 
-#[derive(Component)]
-pub struct Windable;
+/// Wind settings of a level.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindParameters {
+    /// Number of tiles travelled by a gust every second.
+    pub strength: f32,
+    /// Range of the delay between two gusts, in seconds.
+    pub interval: Range<f32>,
+    /// Number of tiles blown by a gust across its direction.
+    pub width: u32,
+    pub nudge_snake: bool,
+    pub blow_apples: bool,
+}
+
+impl Default for WindParameters {
+    fn default() -> Self {
+        Self {
+            strength: 8.0,
+            interval: 5.0..10.0,
+            width: 3,
+            nudge_snake: false,
+            blow_apples: false,
+        }
+    }
+}
+
+impl WindParameters {
+    /// How much a gust stretches the entities it passes through.
+    fn bend_factor(&self) -> f32 {
+        1.0 + (self.strength * 0.06).min(1.0)
+    }
+}
 
-#[derive(Component)]
-pub struct Wind;
+#[derive(Debug, Component)]
+pub struct Windable {
+    pub rest_scale: Vec3,
+}
+
+/// Gust travelling across the tile map, blowing the entities on its front line.
+#[derive(Debug, Component)]
+pub struct Wind {
+    step: Timer,
+    width: u32,
+}
+
+impl Wind {
+    /// Returns whether the given position is on the front line of the gust.
+    fn is_blowing(&self, wind_position: IVec2, direction: Direction, position: IVec2) -> bool {
+        let half_width = (self.width / 2) as i32;
+
+        match direction {
+            Direction::Up | Direction::Down => {
+                position.y == wind_position.y && (position.x - wind_position.x).abs() <= half_width
+            }
+            Direction::Left | Direction::Right => {
+                position.x == wind_position.x && (position.y - wind_position.y).abs() <= half_width
+            }
+        }
+    }
+}
 
 #[derive(Debug, Deref, DerefMut)]
 pub struct WindTimer {
@@ -41,70 +106,203 @@ pub struct WindPlugin;
 
 impl Plugin for WindPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(spawn_wind.run_if(run_criterias::timer_finished::<WindTimer>))
-            .add_system(move_wind.label(GameSystem::Movement))
-            .add_system(apply_wind.after(GameSystem::Movement));
+        app.add_system(
+            spawn_wind
+                .run_if_resource_exists::<WindParameters>()
+                .run_if_resource_exists::<TileMap>()
+                .run_if(run_criterias::timer_finished::<WindTimer>),
+        )
+        .add_system(
+            move_wind
+                .run_if_resource_exists::<TileMap>()
+                .label(GameSystem::Movement),
+        )
+        .add_system_set(
+            ConditionSet::new()
+                .run_if_resource_exists::<WindParameters>()
+                .run_if_resource_exists::<TileMap>()
+                .after(GameSystem::Movement)
+                .before(GameSystem::CheckPosition)
+                .with_system(bend_windables)
+                .with_system(nudge_snake)
+                .with_system(blow_apples)
+                .into(),
+        );
     }
 }
 
-fn spawn_wind(mut commands: Commands, mut timer: ResMut<WindTimer>) {
+/// Spawns a gust just outside of a random edge of the tile map, heading towards the opposite one.
+fn spawn_wind(
+    mut commands: Commands,
+    mut timer: ResMut<WindTimer>,
+    parameters: Res<WindParameters>,
+    tile_map: Res<TileMap>,
+    textures: Res<TextureAssets>,
+    mut rng: ResMut<GameRng>,
+    mut sound_writer: EventWriter<SoundEffect>,
+) {
+    let (width, height) = (tile_map.width() as i32, tile_map.height() as i32);
+
+    let direction = [Direction::Up, Direction::Down, Direction::Left, Direction::Right][rng.gen_range(0..4)];
+    let position = match direction {
+        Direction::Up => IVec3::new(rng.gen_range(0..width), -1, 1),
+        Direction::Down => IVec3::new(rng.gen_range(0..width), height, 1),
+        Direction::Right => IVec3::new(-1, rng.gen_range(0..height), 1),
+        Direction::Left => IVec3::new(width, rng.gen_range(0..height), 1),
+    };
+
+    let width = parameters.width.max(1);
+
     commands
-        .spawn()
-        .insert_bundle((Position(IVec3::new(0, 0, 1)), Direction::Up, Wind, Name::new("Wind")));
+        .spawn_bundle(SpatialBundle::default())
+        .insert_bundle((
+            Position(position),
+            direction,
+            Wind {
+                step: Timer::from_seconds(1.0 / parameters.strength.max(f32::EPSILON), true),
+                width,
+            },
+            ParticleEmitter::new(ParticleConfig::wind_streaks(direction, width), 30.0 * width as f32),
+            Unbounded,
+            Name::new("Wind"),
+        ))
+        .with_children(|parent| {
+            parent.spawn_bundle(SpatialBundle::default()).insert_bundle((
+                ParticleEmitter::new(
+                    ParticleConfig::wind_leaves(direction, width, textures.apple_leaf.clone()),
+                    2.0 * width as f32,
+                ),
+                Name::new("Wind Leaves"),
+            ));
+        });
+
+    sound_writer.send(SoundEffect::Wind);
 
     timer.inner = Timer::from_seconds(timer.generator.generate(), false);
 }
 
-fn move_wind(mut wind_query: Query<(&mut Position, &Direction), With<Wind>>) {
-    wind_query.for_each_mut(|(mut position, direction)| {
+/// Moves the gusts one tile at a time and despawns them once they have crossed the tile map.
+fn move_wind(
+    mut commands: Commands,
+    mut wind_query: Query<(Entity, &mut Position, &Direction, &mut Wind)>,
+    tile_map: Res<TileMap>,
+    time: Res<Time>,
+) {
+    wind_query.for_each_mut(|(entity, mut position, direction, mut wind)| {
+        if !wind.step.tick(time.delta()).just_finished() {
+            return;
+        }
+
         *position += direction.to_ivec3();
+
+        let is_outside = position.x < 0
+            || position.y < 0
+            || position.x >= tile_map.width() as i32
+            || position.y >= tile_map.height() as i32;
+
+        if is_outside {
+            commands.entity(entity).despawn_recursive();
+        }
     });
 }
 
-fn apply_wind(
+fn bend_windables(
     mut commands: Commands,
-    windable_query: Query<(Entity, &Position, &Transform), (With<Windable>, Without<Wind>)>,
-    wind_query: Query<(&Position, &Direction), With<Wind>>,
+    windable_query: Query<(Entity, &Position, &Transform, &Windable), Without<Wind>>,
+    wind_query: Query<(&Position, &Direction, &Wind), Changed<Position>>,
+    parameters: Res<WindParameters>,
 ) {
-    wind_query.for_each(|(position, direction)| {
-        windable_query.for_each(|(entity, windable_position, windable_transform)| {
-            match matches!(direction, Direction::Up | Direction::Down) {
-                true if windable_position.y == position.y => {
-                    println!("youhou");
-                    commands.entity(entity)
-                            .remove::<Windable>()
-                            .insert(Animator::new(Sequence::new([
-                        Tween::new(
-                            EaseFunction::SineIn,
-                            TweeningType::Once,
-                            Duration::from_millis(2500),
-                            TransformScaleLens {
-                                start: windable_transform.scale,
-                                end: Vec3::new(
-                                    windable_transform.scale.x,
-                                    windable_transform.scale.y * 1.5,
-                                    windable_transform.scale.z,
-                                ),
-                            },
-                        ),
-                        Tween::new(
-                            EaseFunction::SineOut,
-                            TweeningType::Once,
-                            Duration::from_millis(2500),
-                            TransformScaleLens {
-                                start: Vec3::new(
-                                    windable_transform.scale.x,
-                                    windable_transform.scale.y * 1.5,
-                                    windable_transform.scale.z,
-                                ),
-                                end: windable_transform.scale,
-                            },
-                        ),
-                    ])));
-                }
-                false if windable_position.x == position.x => {}
-                _ => (),
+    wind_query.for_each(|(wind_position, direction, wind)| {
+        windable_query.for_each(|(entity, position, transform, windable)| {
+            if !wind.is_blowing(wind_position.xy(), *direction, position.xy()) {
+                return;
+            }
+
+            // windables may be rotated, so stretch their local axis which is the closest to the wind one
+            let local_x = transform.rotation * Vec3::X;
+            let is_local_x_vertical = local_x.y.abs() > local_x.x.abs();
+            let is_wind_vertical = matches!(direction, Direction::Up | Direction::Down);
+
+            let rest_scale = windable.rest_scale;
+            let bent_scale = if is_local_x_vertical == is_wind_vertical {
+                rest_scale * Vec3::new(parameters.bend_factor(), 1.0, 1.0)
+            } else {
+                rest_scale * Vec3::new(1.0, parameters.bend_factor(), 1.0)
             };
+
+            commands.entity(entity).insert(Animator::new(Sequence::new([
+                Tween::new(
+                    EaseFunction::SineIn,
+                    TweeningType::Once,
+                    Duration::from_millis(500),
+                    TransformScaleLens {
+                        start: rest_scale,
+                        end: bent_scale,
+                    },
+                ),
+                Tween::new(
+                    EaseFunction::ElasticOut,
+                    TweeningType::Once,
+                    Duration::from_millis(1500),
+                    TransformScaleLens {
+                        start: bent_scale,
+                        end: rest_scale,
+                    },
+                ),
+            ])));
+        });
+    });
+}
+
+/// Turns the snake along the wind when its head is caught in a gust.
+fn nudge_snake(
+    mut snake_query: Query<&mut Snake>,
+    head_query: Query<(&Position, &Direction)>,
+    wind_query: Query<(&Position, &Direction, &Wind), Changed<Position>>,
+    parameters: Res<WindParameters>,
+) {
+    if !parameters.nudge_snake {
+        return;
+    }
+
+    wind_query.for_each(|(wind_position, wind_direction, wind)| {
+        snake_query.for_each_mut(|mut snake| {
+            if let Ok((position, direction)) = head_query.get(snake.fragments[0]) {
+                let is_turnable = *wind_direction != *direction && *wind_direction != direction.opposite();
+
+                if is_turnable && wind.is_blowing(wind_position.xy(), *wind_direction, position.xy()) {
+                    snake.next_direction = Some(*wind_direction);
+                }
+            }
+        });
+    });
+}
+
+/// Pushes the apples caught in a gust one tile further, as long as that tile is free.
+fn blow_apples(
+    mut apple_query: Query<&mut Position, (With<Apple>, Without<Wind>)>,
+    wind_query: Query<(&Position, &Direction, &Wind), Changed<Position>>,
+    parameters: Res<WindParameters>,
+    tile_map: Res<TileMap>,
+) {
+    if !parameters.blow_apples {
+        return;
+    }
+
+    wind_query.for_each(|(wind_position, direction, wind)| {
+        apple_query.for_each_mut(|mut position| {
+            if !wind.is_blowing(wind_position.xy(), *direction, position.xy()) {
+                return;
+            }
+
+            let offset = direction.to_ivec3();
+            let x = (position.x + offset.x).rem_euclid(tile_map.width() as i32) as u32;
+            let y = (position.y + offset.y).rem_euclid(tile_map.height() as i32) as u32;
+
+            if tile_map.tile_at(x, y).is_some_and(Tile::is_empty) {
+                position.x = x as i32;
+                position.y = y as i32;
+            }
         });
     });
 }
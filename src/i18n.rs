@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Language {
+    #[default]
+    English,
+    French,
+}
+
+impl Language {
+    pub const ALL: [Self; 2] = [Self::English, Self::French];
+
+    /// Name of the language, written in that language.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::English => "English",
+            Self::French => "Français",
+        }
+    }
+
+    /// Translates an English text of the user interface, leaving it as is when there is no translation.
+    pub fn translate(self, text: &'static str) -> &'static str {
+        match self {
+            Self::English => text,
+            Self::French => match text {
+                "Play" => "Jouer",
+                "Continue" => "Continuer",
+                "Editor" => "Éditeur",
+                "Settings" => "Options",
+                "Exit" => "Quitter",
+                "Back" => "Retour",
+                "Master volume" => "Volume général",
+                "Music volume" => "Volume de la musique",
+                "Effects volume" => "Volume des effets",
+                "Snake speed" => "Vitesse du serpent",
+                "Slow" => "Lente",
+                "Normal" => "Normale",
+                "Fast" => "Rapide",
+                "Controls" => "Commandes",
+                "Player" => "Joueur",
+                "Up" => "Haut",
+                "Down" => "Bas",
+                "Left" => "Gauche",
+                "Right" => "Droite",
+                "Gamepad" => "Manette",
+                "None" => "Aucune",
+                "Dead zone" => "Zone morte",
+                "Reset" => "Réinitialiser",
+                "Press a key" => "Appuyez sur une touche",
+                "Already used by player" => "Déjà utilisée par le joueur",
+                "Window" => "Fenêtre",
+                "Windowed" => "Fenêtré",
+                "Fullscreen" => "Plein écran",
+                "Colors" => "Couleurs",
+                "Default" => "Par défaut",
+                "Deuteranopia" => "Deutéranopie",
+                "Protanopia" => "Protanopie",
+                "Tritanopia" => "Tritanopie",
+                "Language" => "Langue",
+                "Skin" => "Apparence",
+                "Level reload" => "Rechargement du niveau",
+                "Keep snake" => "Garder le serpent",
+                "Reset snake" => "Replacer le serpent",
+                "Practice mode" => "Mode entraînement",
+                "On" => "Activé",
+                "Off" => "Désactivé",
+                "Game over" => "Partie terminée",
+                "Score" => "Score",
+                "Retry" => "Rejouer",
+                "Main menu" => "Menu principal",
+                "Tutorial" => "Tutoriel",
+                "Press a direction key to turn" => "Appuyez sur une touche de direction pour tourner",
+                "Eat an apple to grow" => "Mangez une pomme pour grandir",
+                "Cross an edge of the map to come out on the other side" => {
+                    "Traversez un bord de la carte pour ressortir de l'autre côté"
+                }
+                "Bushes stop the snake, slip past one without touching it" => {
+                    "Les buissons arrêtent le serpent, frôlez-en un sans le toucher"
+                }
+                "Apples rot after a while, eat one before it disappears" => {
+                    "Les pommes pourrissent au bout d'un moment, mangez-en une avant qu'elle disparaisse"
+                }
+                "Well done! Press Enter to start playing" => "Bravo ! Appuyez sur Entrée pour commencer à jouer",
+                "Press Escape to skip the tutorial" => "Appuyez sur Échap pour passer le tutoriel",
+                "Campaign" => "Campagne",
+                "Locked" => "Verrouillé",
+                "Stars" => "Étoiles",
+                "Reach length" => "Atteindre la taille",
+                "Eat apples" => "Manger des pommes",
+                "Survive" => "Survivre",
+                "Level complete" => "Niveau terminé",
+                "Next level" => "Niveau suivant",
+                "Mode" => "Mode",
+                "Endless" => "Sans fin",
+                "Time attack 60s" => "Contre-la-montre 60 s",
+                "Time attack 120s" => "Contre-la-montre 120 s",
+                "Survival" => "Survie",
+                "Zen" => "Zen",
+                "Time" => "Temps",
+                "High scores" => "Meilleurs scores",
+                "Daily challenge" => "Défi du jour",
+                "Share code" => "Code à partager",
+                "Practice, today's code" => "Entraînement, code du jour",
+                "Ghost" => "Fantôme",
+                "Magnet" => "Aimant",
+                "Slow motion" => "Ralenti",
+                "Score multiplier" => "Multiplicateur",
+                "Shield" => "Bouclier",
+                _ => text,
+            },
+        }
+    }
+}
+
+/// English text of the user interface, displayed in the language of the [`Settings`].
+#[derive(Debug, Component)]
+pub struct Localized(pub &'static str);
+
+pub struct I18nPlugin;
+
+impl Plugin for I18nPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(localize_texts);
+    }
+}
+
+fn localize_texts(settings: Res<Settings>, mut query: Query<(&mut Text, &Localized, ChangeTrackers<Localized>)>) {
+    query.for_each_mut(|(mut text, localized, localized_tracker)| {
+        if settings.is_changed() || localized_tracker.is_changed() {
+            text.sections[0].value = settings.language.translate(localized.0).to_string();
+        }
+    });
+}
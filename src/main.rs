@@ -10,30 +10,66 @@
 #![allow(dead_code)] // TODO
 
 mod assets;
+mod audio;
+mod cli;
+mod display;
 mod game;
+mod i18n;
 mod screens;
+mod settings;
+mod skin;
 mod states;
+mod storage;
 mod systems;
 mod run_criterias;
 
+use bevy::asset::AssetServerSettings;
 use bevy::prelude::*;
 use bevy::window::PresentMode;
 #[cfg(feature = "debug")]
 use bevy_inspector_egui::WorldInspectorPlugin;
 
+use audio::AudioPlugin;
+use cli::{CliError, CliPlugin, LaunchOptions};
+use display::DisplayPlugin;
+use i18n::I18nPlugin;
 use screens::ScreensPlugin;
+use settings::{Settings, SettingsPlugin};
+use skin::SkinPlugin;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 fn main() {
+    let options = match LaunchOptions::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(CliError::Help) => {
+            println!("{}", cli::USAGE);
+
+            return;
+        }
+        Err(e) => {
+            eprintln!("{e}\n\n{}", cli::USAGE);
+
+            std::process::exit(2);
+        }
+    };
+
     let mut app = App::new();
+    let (mut settings, invalid_settings) = Settings::load();
+    let overrides = options.apply_to(&mut settings);
 
     app.insert_resource(WindowDescriptor {
         title: format!("Bevy Snake 2D v{VERSION}"),
         present_mode: PresentMode::AutoVsync,
-        width: 1920.,
-        height: 1080.,
-        resizable: false,
+        width: settings.window.width,
+        height: settings.window.height,
+        mode: settings.window.mode.into(),
+        resizable: true,
+        ..Default::default()
+    })
+    // reload assets, such as levels, as soon as they change on disk while developing
+    .insert_resource(AssetServerSettings {
+        watch_for_changes: cfg!(debug_assertions),
         ..Default::default()
     })
     .insert_resource(Msaa { samples: 4 })
@@ -44,7 +80,20 @@ fn main() {
 
         plugins
     })
-    .add_plugin(ScreensPlugin);
+    .insert_resource(settings)
+    .insert_resource(overrides)
+    .insert_resource(options)
+    .add_plugin(SettingsPlugin)
+    .add_plugin(DisplayPlugin)
+    .add_plugin(I18nPlugin)
+    .add_plugin(AudioPlugin)
+    .add_plugin(SkinPlugin)
+    .add_plugin(ScreensPlugin)
+    .add_plugin(CliPlugin);
+
+    if let Some(invalid_settings) = invalid_settings {
+        app.insert_resource(invalid_settings);
+    }
 
     #[cfg(feature = "debug")]
     app.add_plugin(WorldInspectorPlugin::new());
@@ -0,0 +1,263 @@
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use super::despawn_screen_entities;
+use super::main_menu::{self, ButtonHover, ButtonNormal};
+use crate::assets::FontAssets;
+use crate::display::{ScaledFont, ScaledSize};
+use crate::game::campaign::{Campaign, CampaignAssets, CampaignProgress, CampaignRun, MAX_STARS};
+use crate::settings::Settings;
+use crate::states::AppScreen;
+
+const LOCKED_COLOR: Color = Color::rgb(0.05, 0.05, 0.05);
+const GOAL_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const GOAL_MET_COLOR: Color = Color::rgb(0.5, 0.9, 0.3);
+
+/// Button of the level select screen, `None` going back to the main menu.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Component)]
+struct LevelButton(Option<usize>);
+
+/// Text of a goal of the campaign level being played, by index.
+#[derive(Component)]
+struct GoalText(usize);
+
+pub struct CampaignScreenPlugin;
+
+impl Plugin for CampaignScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_enter_system(AppScreen::LevelSelect, setup_level_select)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppScreen::LevelSelect)
+                    .with_system(select_level)
+                    .with_system(leave_level_select)
+                    .with_system(main_menu::button_interaction)
+                    .into(),
+            )
+            .add_exit_system(AppScreen::LevelSelect, despawn_screen_entities)
+            .add_enter_system(AppScreen::InGame, setup_goal_panel.run_if_resource_exists::<CampaignRun>())
+            .add_system(
+                update_goal_panel
+                    .run_in_state(AppScreen::InGame)
+                    .run_if_resource_exists::<CampaignRun>(),
+            );
+    }
+}
+
+/// Returns the stars earned out of the ones to earn, such as `2/3`, the font having no star glyph.
+pub fn star_text(stars: u8, goal_count: usize) -> String {
+    format!("{stars}/{}", goal_count.min(MAX_STARS))
+}
+
+fn setup_level_select(
+    mut commands: Commands,
+    fonts: Res<FontAssets>,
+    settings: Res<Settings>,
+    campaign_assets: Res<CampaignAssets>,
+    campaigns: Res<Assets<Campaign>>,
+    progress: Res<CampaignProgress>,
+) {
+    let language = settings.language;
+    let text_style = TextStyle {
+        font: fonts.text.clone(),
+        font_size: 32.0,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+
+    let mut buttons = Vec::new();
+
+    if let Some(campaign) = campaigns.get(&campaign_assets.campaign) {
+        for (i, level) in campaign.levels.iter().enumerate() {
+            let text = if progress.is_unlocked(campaign, i) {
+                let stars = star_text(progress.stars(level), level.goals.len());
+
+                format!("{}. {}  ({} {stars})", i + 1, level.name, language.translate("Stars"))
+            } else {
+                format!("{}. {}", i + 1, language.translate("Locked"))
+            };
+
+            buttons.push((LevelButton(Some(i)), text, progress.is_unlocked(campaign, i)));
+        }
+    }
+
+    buttons.push((LevelButton(None), language.translate("Back").to_string(), true));
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            color: UiColor(Color::NONE),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            for (button, text, is_unlocked) in buttons {
+                let normal_color = if is_unlocked {
+                    Color::rgb(0.1, 0.1, 0.1)
+                } else {
+                    LOCKED_COLOR
+                };
+
+                let mut entity = parent.spawn_bundle(ButtonBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(700.0), Val::Px(55.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        margin: UiRect {
+                            bottom: Val::Px(12.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    color: normal_color.into(),
+                    ..default()
+                });
+
+                entity
+                    .with_children(|parent| {
+                        parent
+                            .spawn_bundle(TextBundle::from_section(text, text_style.clone()))
+                            .insert(ScaledFont(32.0));
+                    })
+                    .insert(ScaledSize(Vec2::new(700.0, 55.0)));
+
+                // locked levels neither highlight nor react to clicks
+                if is_unlocked {
+                    entity.insert_bundle((
+                        button,
+                        ButtonNormal(normal_color),
+                        ButtonHover(Color::rgb(0.2, 0.2, 0.2)),
+                    ));
+                }
+            }
+        });
+}
+
+fn select_level(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    campaign_assets: Res<CampaignAssets>,
+    campaigns: Res<Assets<Campaign>>,
+    query: Query<(&Interaction, &LevelButton), Changed<Interaction>>,
+) {
+    query.for_each(|(interaction, button)| {
+        if !matches!(*interaction, Interaction::Clicked) {
+            return;
+        }
+
+        let Some(index) = button.0 else {
+            commands.insert_resource(NextState(AppScreen::MainMenu));
+
+            return;
+        };
+
+        let level = campaigns
+            .get(&campaign_assets.campaign)
+            .and_then(|campaign| campaign.levels.get(index));
+
+        if let Some(level) = level {
+            commands.insert_resource(CampaignRun::new(
+                &asset_server,
+                campaign_assets.campaign.clone(),
+                level,
+                index,
+            ));
+            commands.insert_resource(NextState(AppScreen::InGame));
+        }
+    });
+}
+
+fn leave_level_select(mut commands: Commands, keys: Res<Input<KeyCode>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        commands.insert_resource(NextState(AppScreen::MainMenu));
+    }
+}
+
+/// Lists the goals of the level in a corner of the game, along with the progress toward each of them.
+fn setup_goal_panel(
+    mut commands: Commands,
+    fonts: Res<FontAssets>,
+    campaign_run: Res<CampaignRun>,
+    campaigns: Res<Assets<Campaign>>,
+) {
+    let Some(level) = campaign_run.level(&campaigns) else {
+        return;
+    };
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(20.0),
+                    top: Val::Px(20.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            color: UiColor(Color::NONE),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    level.name.clone(),
+                    TextStyle {
+                        font: fonts.text.clone(),
+                        font_size: 32.0,
+                        color: Color::WHITE,
+                    },
+                ))
+                .insert(ScaledFont(32.0));
+
+            for i in 0..level.goals.len() {
+                parent
+                    .spawn_bundle(TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: fonts.text.clone(),
+                            font_size: 24.0,
+                            color: GOAL_COLOR,
+                        },
+                    ))
+                    .insert_bundle((GoalText(i), ScaledFont(24.0)));
+            }
+        });
+}
+
+fn update_goal_panel(
+    settings: Res<Settings>,
+    campaign_run: Res<CampaignRun>,
+    campaigns: Res<Assets<Campaign>>,
+    mut query: Query<(&mut Text, &GoalText)>,
+) {
+    if !campaign_run.is_changed() && !settings.is_changed() {
+        return;
+    }
+
+    let Some(level) = campaign_run.level(&campaigns) else {
+        return;
+    };
+
+    query.for_each_mut(|(mut text, goal_text)| {
+        if let Some(goal) = level.goals.get(goal_text.0) {
+            let section = &mut text.sections[0];
+
+            section.value = format!(
+                "{}: {}",
+                settings.language.translate(goal.label()),
+                goal.progress(&campaign_run.progress)
+            );
+            section.style.color = if goal.is_met(&campaign_run.progress) {
+                GOAL_MET_COLOR
+            } else {
+                GOAL_COLOR
+            };
+        }
+    });
+}
@@ -0,0 +1,309 @@
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+use leafwing_input_manager::Actionlike;
+
+use super::despawn_screen_entities;
+use super::main_menu::{self, ButtonHover, ButtonNormal};
+use super::settings::next;
+use crate::assets::FontAssets;
+use crate::display::{ScaledFont, ScaledSize};
+use crate::game::input_controller::{ControlSettings, MAX_GAMEPADS};
+use crate::game::snake::SnakeAction;
+use crate::settings::Settings;
+use crate::states::AppScreen;
+use crate::systems;
+
+const DEAD_ZONES: [f32; 5] = [0.1, 0.2, 0.3, 0.4, 0.5];
+
+/// Binding changed by clicking a button of the controls screen.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Component)]
+enum ControlOption {
+    Key(usize, SnakeAction),
+    Gamepad(usize),
+    DeadZone(usize),
+    Reset,
+    Back,
+}
+
+impl ControlOption {
+    fn text(self, settings: &Settings) -> String {
+        let language = settings.language;
+
+        match self {
+            Self::Key(player, action) => format!(
+                "{}: {:?}",
+                language.translate(action.name()),
+                settings.controls.player(player).key(action)
+            ),
+            Self::Gamepad(player) => format!(
+                "{}: {}",
+                language.translate("Gamepad"),
+                settings.controls.player(player).gamepad.map_or_else(
+                    || language.translate("None").to_string(),
+                    |gamepad| (gamepad + 1).to_string()
+                )
+            ),
+            Self::DeadZone(player) => format!(
+                "{}: {:.0}%",
+                language.translate("Dead zone"),
+                settings.controls.player(player).dead_zone * 100.0
+            ),
+            Self::Reset => language.translate("Reset").to_string(),
+            Self::Back => language.translate("Back").to_string(),
+        }
+    }
+}
+
+#[derive(Component)]
+struct OptionText(ControlOption);
+
+#[derive(Component)]
+struct StatusText;
+
+/// Key binding waiting for the player to press a key.
+#[derive(Debug, Copy, Clone)]
+struct PendingBinding {
+    player: usize,
+    action: SnakeAction,
+}
+
+pub struct ControlsScreenPlugin;
+
+impl Plugin for ControlsScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_enter_system(AppScreen::Controls, setup_controls)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppScreen::Controls)
+                    .with_system(change_option)
+                    .with_system(bind_key.run_if_resource_exists::<PendingBinding>())
+                    .with_system(leave_controls.run_unless_resource_exists::<PendingBinding>())
+                    .with_system(update_option_texts)
+                    .with_system(main_menu::button_interaction)
+                    .into(),
+            )
+            .add_exit_system(AppScreen::Controls, despawn_screen_entities)
+            .add_exit_system(AppScreen::Controls, systems::despawn_resource::<PendingBinding>);
+    }
+}
+
+fn setup_controls(mut commands: Commands, fonts: Res<FontAssets>, settings: Res<Settings>) {
+    let text_style = TextStyle {
+        font: fonts.text.clone(),
+        font_size: 32.0,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            color: UiColor(Color::NONE),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        justify_content: JustifyContent::Center,
+                        flex_direction: FlexDirection::Row,
+                        ..Default::default()
+                    },
+                    color: UiColor(Color::NONE),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    for player in 0..settings.controls.players.len() {
+                        parent
+                            .spawn_bundle(NodeBundle {
+                                style: Style {
+                                    align_items: AlignItems::Center,
+                                    flex_direction: FlexDirection::ColumnReverse,
+                                    margin: UiRect::new(Val::Px(20.0), Val::Px(20.0), Val::Px(0.0), Val::Px(20.0)),
+                                    ..Default::default()
+                                },
+                                color: UiColor(Color::NONE),
+                                ..Default::default()
+                            })
+                            .with_children(|parent| {
+                                let title = format!("{} {}", settings.language.translate("Player"), player + 1);
+
+                                parent
+                                    .spawn_bundle(TextBundle::from_section(title, text_style.clone()))
+                                    .insert(ScaledFont(32.0));
+
+                                for action in SnakeAction::variants() {
+                                    spawn_option(parent, &text_style, ControlOption::Key(player, action), &settings);
+                                }
+
+                                spawn_option(parent, &text_style, ControlOption::Gamepad(player), &settings);
+                                spawn_option(parent, &text_style, ControlOption::DeadZone(player), &settings);
+                            });
+                    }
+                });
+
+            spawn_option(parent, &text_style, ControlOption::Reset, &settings);
+            spawn_option(parent, &text_style, ControlOption::Back, &settings);
+
+            parent
+                .spawn_bundle(TextBundle::from_section("", text_style.clone()))
+                .insert_bundle((StatusText, ScaledFont(32.0)));
+        });
+}
+
+fn spawn_option(parent: &mut ChildBuilder, text_style: &TextStyle, option: ControlOption, settings: &Settings) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(400.0), Val::Px(50.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                margin: UiRect {
+                    bottom: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle::from_section(option.text(settings), text_style.clone()))
+                .insert_bundle((OptionText(option), ScaledFont(32.0)));
+        })
+        .insert_bundle((
+            option,
+            ScaledSize(Vec2::new(400.0, 50.0)),
+            ButtonNormal(Color::rgb(0.1, 0.1, 0.1)),
+            ButtonHover(Color::rgb(0.2, 0.2, 0.2)),
+        ));
+}
+
+fn change_option(
+    mut commands: Commands,
+    mut settings: ResMut<Settings>,
+    query: Query<(&Interaction, &ControlOption), Changed<Interaction>>,
+    mut option_text_query: Query<(&mut Text, &OptionText)>,
+    mut status_query: Query<&mut Text, (With<StatusText>, Without<OptionText>)>,
+) {
+    query.for_each(|(interaction, option)| {
+        if !matches!(*interaction, Interaction::Clicked) {
+            return;
+        }
+
+        match *option {
+            ControlOption::Key(player, action) => {
+                let prompt = settings.language.translate("Press a key");
+
+                option_text_query.for_each_mut(|(mut text, option_text)| {
+                    if option_text.0 == *option {
+                        text.sections[0].value = format!("{}: ...", settings.language.translate(action.name()));
+                    }
+                });
+
+                status_query.for_each_mut(|mut text| text.sections[0].value = prompt.to_string());
+                commands.insert_resource(PendingBinding { player, action });
+            }
+            ControlOption::Gamepad(player) => {
+                let controls = &mut settings.controls;
+                let gamepad = next_free_gamepad(controls, player);
+
+                if let Some(bindings) = controls.players.get_mut(player) {
+                    bindings.gamepad = gamepad;
+                }
+            }
+            ControlOption::DeadZone(player) => {
+                if let Some(bindings) = settings.controls.players.get_mut(player) {
+                    bindings.dead_zone = next(&DEAD_ZONES, bindings.dead_zone);
+                }
+            }
+            ControlOption::Reset => {
+                settings.controls = ControlSettings::default();
+                status_query.for_each_mut(|mut text| text.sections[0].value.clear());
+            }
+            ControlOption::Back => commands.insert_resource(NextState(AppScreen::Settings)),
+        }
+    });
+}
+
+/// Returns the gamepad following the current one of the player that no other player uses, `None` meaning the
+/// keyboard alone.
+fn next_free_gamepad(controls: &ControlSettings, player: usize) -> Option<usize> {
+    let current = controls.player(player).gamepad;
+    let candidates = std::iter::once(None)
+        .chain((0..MAX_GAMEPADS).map(Some))
+        .collect::<Vec<_>>();
+    let i = candidates
+        .iter()
+        .position(|gamepad| *gamepad == current)
+        .unwrap_or_default();
+
+    candidates
+        .iter()
+        .cycle()
+        .skip(i + 1)
+        .take(candidates.len())
+        .copied()
+        .find(|gamepad| gamepad.is_none_or(|gamepad| controls.gamepad_conflict(player, gamepad).is_none()))
+        .flatten()
+}
+
+fn bind_key(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    pending_binding: Res<PendingBinding>,
+    mut settings: ResMut<Settings>,
+    mut status_query: Query<&mut Text, With<StatusText>>,
+) {
+    let key = match keys.get_just_pressed().next() {
+        Some(key) => *key,
+        None => return,
+    };
+
+    let PendingBinding { player, action } = *pending_binding;
+    let conflict = settings.controls.key_conflict(player, action, key);
+
+    let status = match conflict {
+        _ if key == KeyCode::Escape => String::new(),
+        Some((other_player, other_action)) => format!(
+            "{key:?}: {} {} ({})",
+            settings.language.translate("Already used by player"),
+            other_player + 1,
+            settings.language.translate(other_action.name())
+        ),
+        None => {
+            if let Some(bindings) = settings.controls.players.get_mut(player) {
+                bindings.set_key(action, key);
+            }
+
+            String::new()
+        }
+    };
+
+    status_query.for_each_mut(|mut text| text.sections[0].value.clone_from(&status));
+
+    // refresh the text of the pending binding even if it was cancelled or refused
+    settings.set_changed();
+    commands.remove_resource::<PendingBinding>();
+}
+
+fn update_option_texts(settings: Res<Settings>, mut query: Query<(&mut Text, &OptionText)>) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    query.for_each_mut(|(mut text, option_text)| {
+        text.sections[0].value = option_text.0.text(&settings);
+    });
+}
+
+fn leave_controls(mut commands: Commands, keys: Res<Input<KeyCode>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        commands.insert_resource(NextState(AppScreen::Settings));
+    }
+}
@@ -0,0 +1,392 @@
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use super::{despawn_screen_entities, reset_main_camera, MainCamera};
+use super::in_game::Playtest;
+use crate::assets::{FontAssets, SnakeAtlas, TextureAssets};
+use crate::display::ScaledFont;
+use crate::game::camera;
+use crate::game::components::Position;
+use crate::game::level::{self, Level, LevelEntity, Terrain, TileEntity, MIN_SNAKE_LENGTH};
+use crate::game::tile_map::{TileMap, TileMapOptions, TileMapPosition, TILE_SIZE};
+use crate::states::AppScreen;
+use crate::storage;
+use crate::systems;
+
+/// File of the level being edited, in the storage rather than in the assets which may not be writable.
+const CUSTOM_LEVEL_FILE: &str = "custom_level.json";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Brush {
+    Bush,
+    Terrain(Terrain),
+    SnakeSpawn,
+}
+
+/// Level being edited, kept across playtests.
+struct Editor {
+    level: Level,
+    path: PathBuf,
+    brush: Brush,
+    status: String,
+}
+
+impl Editor {
+    fn open(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+
+        let (level, status) = match Level::load(&path) {
+            Ok(level) => (level, format!("Opened {}", path.display())),
+            Err(e) => (Level::default(), format!("New level ({e})")),
+        };
+
+        Self {
+            level,
+            path,
+            brush: Brush::Bush,
+            status,
+        }
+    }
+}
+
+#[derive(Component)]
+struct EditorCamera;
+
+#[derive(Component)]
+struct EditorStatus;
+
+#[derive(Component)]
+struct SnakePreview;
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_enter_system(AppScreen::Editor, setup_editor)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppScreen::Editor)
+                    .run_if_resource_exists::<Editor>()
+                    .with_system(select_brush)
+                    .with_system(paint_tiles)
+                    .with_system(resize_level)
+                    .with_system(edit_snake_spawn)
+                    .with_system(save_level)
+                    .with_system(start_playtest)
+                    .with_system(leave_editor)
+                    .with_system(update_preview)
+                    .with_system(update_status)
+                    .into(),
+            )
+            .add_exit_system_set(
+                AppScreen::Editor,
+                SystemSet::new()
+                    .with_system(despawn_screen_entities)
+                    .with_system(systems::remove_component::<EditorCamera>)
+                    .with_system(reset_main_camera)
+                    .with_system(systems::despawn_resource::<TileMap>),
+            );
+    }
+}
+
+fn setup_editor(
+    mut commands: Commands,
+    editor: Option<ResMut<Editor>>,
+    fonts: Res<FontAssets>,
+    camera_query: Query<Entity, With<MainCamera>>,
+) {
+    camera_query.for_each(|entity| {
+        commands.entity(entity).insert(EditorCamera);
+    });
+
+    // force the preview to be rebuilt when coming back from a playtest
+    match editor {
+        Some(mut editor) => editor.set_changed(),
+        None => commands.insert_resource(Editor::open(storage::path(CUSTOM_LEVEL_FILE))),
+    }
+
+    commands.insert_resource(TileMapOptions {
+        tile_size: TILE_SIZE,
+        position: TileMapPosition::Centered,
+    });
+
+    commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: fonts.text.clone(),
+                    font_size: 24.0,
+                    color: Color::WHITE,
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(10.0),
+                    top: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            }),
+        )
+        .insert_bundle((EditorStatus, ScaledFont(24.0)));
+}
+
+fn select_brush(keys: Res<Input<KeyCode>>, mut editor: ResMut<Editor>) {
+    let brush = if keys.just_pressed(KeyCode::Key1) {
+        Brush::Bush
+    } else if keys.just_pressed(KeyCode::Key2) {
+        Brush::Terrain(Terrain::Grass)
+    } else if keys.just_pressed(KeyCode::Key3) {
+        Brush::Terrain(Terrain::Dirt)
+    } else if keys.just_pressed(KeyCode::Key4) {
+        Brush::Terrain(Terrain::Sand)
+    } else if keys.just_pressed(KeyCode::Key5) {
+        Brush::SnakeSpawn
+    } else {
+        return;
+    };
+
+    editor.brush = brush;
+}
+
+fn paint_tiles(
+    mut editor: ResMut<Editor>,
+    buttons: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<EditorCamera>>,
+    tile_map: Option<Res<TileMap>>,
+    tile_map_options: Res<TileMapOptions>,
+) {
+    let (Some(tile_map), Some(window)) = (tile_map, windows.get_primary()) else {
+        return;
+    };
+
+    let is_painting = buttons.pressed(MouseButton::Left);
+    let is_erasing = buttons.pressed(MouseButton::Right);
+
+    if !is_painting && !is_erasing {
+        return;
+    }
+
+    let position = camera_query
+        .get_single()
+        .ok()
+        .and_then(|(camera, transform)| cursor_to_world(window, camera, transform))
+        .and_then(|position| tile_map_options.world_to_tile(&tile_map, position));
+
+    let (x, y) = match position {
+        Some(position) => (position.x, position.y),
+        None => return,
+    };
+
+    // only touch the editor when something changes to avoid rebuilding the preview every frame
+    if is_erasing {
+        if editor.level.tile_at(x, y).is_some() {
+            editor.level.set_tile_at(x, y, None);
+        }
+        if editor.level.terrain_at(x, y) != Terrain::default() {
+            editor.level.set_terrain_at(x, y, Terrain::default());
+        }
+
+        return;
+    }
+
+    let brush = editor.brush;
+
+    match brush {
+        Brush::Bush if editor.level.tile_at(x, y).is_none() => {
+            editor.level.set_tile_at(x, y, Some(TileEntity::Bush));
+        }
+        Brush::Terrain(terrain) if editor.level.terrain_at(x, y) != terrain => {
+            editor.level.set_terrain_at(x, y, terrain);
+        }
+        Brush::SnakeSpawn => {
+            let mut snake_spawn = editor.level.snake_spawn();
+
+            if (snake_spawn.x, snake_spawn.y) != (x, y) {
+                snake_spawn.x = x;
+                snake_spawn.y = y;
+                editor.level.snake = Some(snake_spawn);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn resize_level(keys: Res<Input<KeyCode>>, mut editor: ResMut<Editor>) {
+    let (mut width, mut height) = (editor.level.width, editor.level.height);
+
+    if keys.just_pressed(KeyCode::Right) {
+        width += 1;
+    } else if keys.just_pressed(KeyCode::Left) {
+        width = width.saturating_sub(1);
+    } else if keys.just_pressed(KeyCode::Up) {
+        height += 1;
+    } else if keys.just_pressed(KeyCode::Down) {
+        height = height.saturating_sub(1);
+    } else {
+        return;
+    }
+
+    editor.level.resize(width, height);
+}
+
+fn edit_snake_spawn(keys: Res<Input<KeyCode>>, mut editor: ResMut<Editor>) {
+    let mut snake_spawn = editor.level.snake_spawn();
+
+    let mut length = snake_spawn.length.get();
+
+    if keys.just_pressed(KeyCode::R) {
+        snake_spawn.direction = snake_spawn.direction.clockwise();
+    } else if keys.just_pressed(KeyCode::Equals) {
+        length += 1;
+    } else if keys.just_pressed(KeyCode::Minus) {
+        length -= 1;
+    } else {
+        return;
+    }
+
+    // the body lies along the direction of the snake, which must fit in the level
+    let max_length = snake_spawn.max_length(editor.level.width, editor.level.height);
+
+    snake_spawn.length = NonZeroUsize::new(length.clamp(MIN_SNAKE_LENGTH, max_length)).unwrap();
+
+    editor.level.snake = Some(snake_spawn);
+}
+
+fn save_level(keys: Res<Input<KeyCode>>, mut editor: ResMut<Editor>) {
+    let is_control_pressed = keys.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+
+    if is_control_pressed && keys.just_pressed(KeyCode::S) {
+        editor.status = match editor.level.save(&editor.path) {
+            Ok(()) => format!("Saved {}", editor.path.display()),
+            Err(e) => format!("Cannot save {}: {e}", editor.path.display()),
+        };
+    }
+}
+
+fn start_playtest(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    editor: Res<Editor>,
+    mut levels: ResMut<Assets<Level>>,
+) {
+    if keys.just_pressed(KeyCode::F5) {
+        commands.insert_resource(Playtest(levels.add(editor.level.clone())));
+        commands.insert_resource(NextState(AppScreen::InGame));
+    }
+}
+
+fn leave_editor(mut commands: Commands, keys: Res<Input<KeyCode>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        commands.insert_resource(NextState(AppScreen::MainMenu));
+    }
+}
+
+fn update_preview(
+    mut commands: Commands,
+    editor: Res<Editor>,
+    textures: Res<TextureAssets>,
+    snake_atlas: Option<Res<SnakeAtlas>>,
+    windows: Res<Windows>,
+    preview_query: Query<Entity, Or<(With<LevelEntity>, With<SnakePreview>)>>,
+    mut camera_query: Query<&mut OrthographicProjection, With<EditorCamera>>,
+) {
+    if !editor.is_changed() {
+        return;
+    }
+
+    preview_query.for_each(|entity| commands.entity(entity).despawn_recursive());
+
+    let tile_map = level::spawn_level(&mut commands, &textures, &editor.level);
+
+    let snake_spawn = editor.level.snake_spawn();
+    let offset = IVec3::from(snake_spawn.direction.opposite());
+    let length = snake_spawn.length.get();
+
+    // the snake shows up once its skin is packed, which happens long before the editor can be opened
+    let Some(snake_atlas) = &snake_atlas else {
+        commands.insert_resource(tile_map);
+
+        return;
+    };
+
+    for i in 0..length {
+        let index = match i {
+            0 => snake_atlas.head,
+            i if i < length - 1 => snake_atlas.fragments[i % snake_atlas.fragments.len()].straight,
+            _ => snake_atlas.tail,
+        };
+
+        commands
+            .spawn_bundle(SpriteSheetBundle {
+                sprite: TextureAtlasSprite::new(index),
+                texture_atlas: snake_atlas.atlas.clone(),
+                transform: Transform::from_rotation(snake_spawn.direction.to_quat()),
+                ..default()
+            })
+            .insert_bundle((
+                Position(snake_spawn.position() + offset * i as i32),
+                SnakePreview,
+                Name::new("Snake Preview"),
+            ));
+    }
+
+    // leave some room around the level for the status text
+    if let (Some(window), Ok(mut projection)) = (windows.get_primary(), camera_query.get_single_mut()) {
+        let size = Vec2::new(tile_map.width() as f32, tile_map.height() as f32) * TILE_SIZE;
+
+        projection.scale = camera::fit_scale(size, window, 1.25);
+    }
+
+    commands.insert_resource(tile_map);
+}
+
+fn update_status(editor: Res<Editor>, mut query: Query<&mut Text, With<EditorStatus>>) {
+    if !editor.is_changed() {
+        return;
+    }
+
+    let brush = match editor.brush {
+        Brush::Bush => "bush",
+        Brush::Terrain(Terrain::Grass) => "grass",
+        Brush::Terrain(Terrain::Dirt) => "dirt",
+        Brush::Terrain(Terrain::Sand) => "sand",
+        Brush::SnakeSpawn => "snake spawn",
+    };
+    let snake_spawn = editor.level.snake_spawn();
+
+    query.for_each_mut(|mut text| {
+        text.sections[0].value = format!(
+            "{}\n\
+             size: {}x{} | brush: {brush} | snake: ({}, {}) {} x{}\n\
+             [1-5] brush  [LMB/RMB] paint/erase  [arrows] resize  [R] rotate snake  [+/-] snake length\n\
+             [Ctrl+S] save  [F5] playtest  [Esc] main menu\n\
+             {}",
+            editor.path.display(),
+            editor.level.width,
+            editor.level.height,
+            snake_spawn.x,
+            snake_spawn.y,
+            snake_spawn.direction,
+            snake_spawn.length,
+            editor.status,
+        );
+    });
+}
+
+fn cursor_to_world(window: &Window, camera: &Camera, camera_transform: &GlobalTransform) -> Option<Vec2> {
+    let cursor_position = window.cursor_position()?;
+    let window_size = Vec2::new(window.width(), window.height());
+
+    // convert the cursor position into normalized device coordinates, then back into the world
+    let ndc = (cursor_position / window_size) * 2.0 - Vec2::ONE;
+    let ndc_to_world = camera_transform.compute_matrix() * camera.projection_matrix().inverse();
+
+    Some(ndc_to_world.project_point3(ndc.extend(-1.0)).truncate())
+}
@@ -0,0 +1,356 @@
+use std::marker::PhantomData;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use super::campaign::star_text;
+use super::main_menu;
+use crate::assets::FontAssets;
+use crate::display::ScaledFont;
+use crate::game::campaign::{Campaign, CampaignLevel, CampaignProgress, CampaignRun};
+use crate::game::daily::{DailyResults, DailyRun};
+use crate::game::play_mode::{HighScores, PlayMode, Ranked};
+use crate::game::GameSystem;
+use crate::game::score::Score;
+use crate::i18n::Localized;
+use crate::settings::Settings;
+use crate::states::{AppScreen, GameStatus};
+use crate::systems;
+
+const NEW_HIGH_SCORE_COLOR: Color = Color::rgb(1.0, 0.8, 0.2);
+
+/// Root of the game over overlay, drawn on top of the remains of the game.
+#[derive(Component)]
+struct GameOverOverlay;
+
+#[derive(Component)]
+struct RetryButton;
+
+#[derive(Component)]
+struct NextLevelButton;
+
+#[derive(Component)]
+struct MainMenuButton;
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_enter_system(GameStatus::GameOver, setup_game_over.after(GameSystem::RecordScore))
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppScreen::InGame)
+                    .run_in_state(GameStatus::GameOver)
+                    .with_system(retry)
+                    .with_system(next_level.run_if_resource_exists::<CampaignRun>())
+                    .with_system(back_to_main_menu)
+                    .with_system(main_menu::button_interaction)
+                    .into(),
+            )
+            .add_exit_system(GameStatus::GameOver, systems::despawn_with::<GameOverOverlay>);
+    }
+}
+
+/// Restarts the game by entering the game screen again, which tears the current game down first.
+fn retry(mut commands: Commands, query: Query<&Interaction, (Changed<Interaction>, With<RetryButton>)>) {
+    query.for_each(|interaction| {
+        if matches!(*interaction, Interaction::Clicked) {
+            commands.insert_resource(NextState(AppScreen::InGame));
+        }
+    });
+}
+
+/// Plays the campaign level following the one just played.
+fn next_level(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    campaign_run: Res<CampaignRun>,
+    campaigns: Res<Assets<Campaign>>,
+    query: Query<&Interaction, (Changed<Interaction>, With<NextLevelButton>)>,
+) {
+    if !query
+        .iter()
+        .any(|interaction| matches!(*interaction, Interaction::Clicked))
+    {
+        return;
+    }
+
+    let index = campaign_run.index + 1;
+    let level = campaigns
+        .get(&campaign_run.campaign)
+        .and_then(|campaign| campaign.levels.get(index));
+
+    if let Some(level) = level {
+        commands.insert_resource(CampaignRun::new(
+            &asset_server,
+            campaign_run.campaign.clone(),
+            level,
+            index,
+        ));
+        commands.insert_resource(NextState(AppScreen::InGame));
+    }
+}
+
+fn back_to_main_menu(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    query: Query<&Interaction, (Changed<Interaction>, With<MainMenuButton>)>,
+) {
+    if keys.just_pressed(KeyCode::Escape)
+        || query
+            .iter()
+            .any(|interaction| matches!(*interaction, Interaction::Clicked))
+    {
+        commands.insert_resource(NextState(AppScreen::MainMenu));
+    }
+}
+
+/// Campaign level just played, if any.
+#[derive(SystemParam)]
+struct CampaignOutcome<'w, 's> {
+    campaign_run: Option<Res<'w, CampaignRun>>,
+    campaigns: Res<'w, Assets<Campaign>>,
+    progress: Res<'w, CampaignProgress>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl CampaignOutcome<'_, '_> {
+    fn level(&self) -> Option<(&CampaignRun, &CampaignLevel)> {
+        let campaign_run = self.campaign_run.as_deref()?;
+
+        Some((campaign_run, campaign_run.level(&self.campaigns)?))
+    }
+
+    fn is_completed(&self) -> bool {
+        self.level()
+            .is_some_and(|(campaign_run, level)| level.is_completed(&campaign_run.progress))
+    }
+
+    fn has_next_level(&self) -> bool {
+        self.campaign_run.as_ref().is_some_and(|campaign_run| {
+            self.campaigns
+                .get(&campaign_run.campaign)
+                .is_some_and(|campaign| self.progress.is_unlocked(campaign, campaign_run.index + 1))
+        })
+    }
+}
+
+/// High scores of the mode just played, listed if the game was ranked.
+#[derive(SystemParam)]
+struct RankedOutcome<'w, 's> {
+    ranked: Option<Res<'w, Ranked>>,
+    settings: Res<'w, Settings>,
+    mode: Res<'w, PlayMode>,
+    high_scores: Res<'w, HighScores>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+/// Daily challenge just played, if any.
+#[derive(SystemParam)]
+struct DailyOutcome<'w, 's> {
+    daily_run: Option<Res<'w, DailyRun>>,
+    results: Res<'w, DailyResults>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+fn setup_game_over(
+    mut commands: Commands,
+    fonts: Res<FontAssets>,
+    score: Option<Res<Score>>,
+    campaign: CampaignOutcome,
+    ranked: RankedOutcome,
+    daily: DailyOutcome,
+) {
+    let level = campaign.level();
+    let is_completed = campaign.is_completed();
+    let has_next_level = campaign.has_next_level();
+    // the stars, the share code or the high scores follow the score
+    let has_details = level.is_some() || ranked.ranked.is_some() || daily.daily_run.is_some();
+    let title = if is_completed { "Level complete" } else { "Game over" };
+    let title_color = if is_completed {
+        Color::rgb(0.5, 0.9, 0.3)
+    } else {
+        Color::rgb(0.9, 0.3, 0.3)
+    };
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            color: UiColor(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+            ..Default::default()
+        })
+        .insert(GameOverOverlay)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    title,
+                    TextStyle {
+                        font: fonts.text.clone(),
+                        font_size: 80.0,
+                        color: title_color,
+                    },
+                ))
+                .insert_bundle((ScaledFont(80.0), Localized(title)));
+
+            let text_style = TextStyle {
+                font: fonts.text.clone(),
+                font_size: 40.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            };
+
+            // only the first section is localized, the score follows it
+            parent
+                .spawn_bundle(
+                    TextBundle::from_sections([
+                        TextSection::new("Score", text_style.clone()),
+                        TextSection::new(format!(": {}", score.map_or(0, |score| score.0)), text_style.clone()),
+                    ])
+                    .with_style(Style {
+                        margin: UiRect {
+                            top: Val::Px(20.0),
+                            bottom: Val::Px(if has_details { 10.0 } else { 40.0 }),
+                            ..default()
+                        },
+                        ..default()
+                    }),
+                )
+                .insert_bundle((ScaledFont(40.0), Localized("Score")));
+
+            if let Some((campaign_run, level)) = level {
+                let stars = star_text(campaign_run.result.unwrap_or_default(), level.goals.len());
+
+                parent
+                    .spawn_bundle(
+                        TextBundle::from_sections([
+                            TextSection::new("Stars", text_style.clone()),
+                            TextSection::new(format!(": {stars}"), text_style.clone()),
+                        ])
+                        .with_style(Style {
+                            margin: UiRect {
+                                bottom: Val::Px(40.0),
+                                ..default()
+                            },
+                            ..default()
+                        }),
+                    )
+                    .insert_bundle((ScaledFont(40.0), Localized("Stars")));
+            }
+
+            if let Some(daily_run) = daily.daily_run.as_ref() {
+                spawn_daily_result(parent, &text_style, daily_run, &daily.results);
+            }
+
+            if ranked.ranked.is_some() {
+                spawn_high_scores(parent, &text_style, &ranked.settings, *ranked.mode, &ranked.high_scores);
+            }
+
+            if has_next_level {
+                main_menu::spawn_button(parent, &fonts, "Next level").insert(NextLevelButton);
+            }
+
+            main_menu::spawn_button(parent, &fonts, "Retry").insert(RetryButton);
+            main_menu::spawn_button(parent, &fonts, "Main menu").insert(MainMenuButton);
+        });
+}
+
+/// Lists the best scores of the mode, highlighting the one just made.
+fn spawn_high_scores(
+    parent: &mut ChildBuilder,
+    text_style: &TextStyle,
+    settings: &Settings,
+    mode: PlayMode,
+    high_scores: &HighScores,
+) {
+    let text_style = TextStyle {
+        font_size: 32.0,
+        ..text_style.clone()
+    };
+
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                margin: UiRect {
+                    bottom: Val::Px(40.0),
+                    ..default()
+                },
+                ..Default::default()
+            },
+            color: UiColor(Color::NONE),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            // only the first section is localized, the mode follows it
+            parent
+                .spawn_bundle(TextBundle::from_sections([
+                    TextSection::new("High scores", text_style.clone()),
+                    TextSection::new(
+                        format!(" ({})", settings.language.translate(mode.name())),
+                        text_style.clone(),
+                    ),
+                ]))
+                .insert_bundle((ScaledFont(32.0), Localized("High scores")));
+
+            for (rank, score) in high_scores.table(mode).iter().enumerate() {
+                let color = if high_scores.last_entry == Some((mode, rank)) {
+                    NEW_HIGH_SCORE_COLOR
+                } else {
+                    text_style.color
+                };
+
+                parent
+                    .spawn_bundle(TextBundle::from_section(
+                        format!("{}. {score}", rank + 1),
+                        TextStyle {
+                            color,
+                            ..text_style.clone()
+                        },
+                    ))
+                    .insert(ScaledFont(32.0));
+            }
+        });
+}
+
+/// Shows the share code of the daily challenge, practice games showing the one of the scored attempt.
+fn spawn_daily_result(parent: &mut ChildBuilder, text_style: &TextStyle, daily_run: &DailyRun, results: &DailyResults) {
+    let text_style = TextStyle {
+        font_size: 32.0,
+        ..text_style.clone()
+    };
+    let label = if daily_run.is_scored {
+        "Share code"
+    } else {
+        "Practice, today's code"
+    };
+    let code = results
+        .result(daily_run.date)
+        .map_or_else(String::new, |result| result.code());
+
+    // only the first section is localized, the code follows it
+    parent
+        .spawn_bundle(
+            TextBundle::from_sections([
+                TextSection::new(label, text_style.clone()),
+                TextSection::new(format!(": {code}"), text_style),
+            ])
+            .with_style(Style {
+                margin: UiRect {
+                    bottom: Val::Px(40.0),
+                    ..default()
+                },
+                ..default()
+            }),
+        )
+        .insert_bundle((ScaledFont(32.0), Localized(label)));
+}
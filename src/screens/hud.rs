@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::assets::FontAssets;
+use crate::display::ScaledFont;
+use crate::game::play_mode::ModeClock;
+use crate::game::power_up::{
+    Effect, Ghost, Magnet, PowerUp, ScoreMultiplier, Shield, SlowMotion, Stacking, TimedEffect,
+};
+use crate::game::score::Score;
+use crate::i18n::Localized;
+use crate::states::AppScreen;
+
+#[derive(Component)]
+struct ScoreText;
+
+/// Time left in a time attack.
+#[derive(Component)]
+struct ClockText;
+
+/// Time left before the effect of a power-up wears off, shown while it is active.
+#[derive(Component)]
+struct EffectText(PowerUp);
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_enter_system(AppScreen::InGame, setup_hud).add_system_set(
+            ConditionSet::new()
+                .run_in_state(AppScreen::InGame)
+                .with_system(update_score_text)
+                .with_system(update_clock_text)
+                .with_system(update_effect_text::<Ghost>)
+                .with_system(update_effect_text::<Magnet>)
+                .with_system(update_effect_text::<SlowMotion>)
+                .with_system(update_effect_text::<ScoreMultiplier>)
+                .with_system(update_effect_text::<Shield>)
+                .into(),
+        );
+    }
+}
+
+/// Lists the score, the time left if the game is limited and the active effects, in a corner of the game.
+fn setup_hud(mut commands: Commands, fonts: Res<FontAssets>) {
+    let text_style = TextStyle {
+        font: fonts.text.clone(),
+        font_size: 32.0,
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    right: Val::Px(20.0),
+                    top: Val::Px(20.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::FlexEnd,
+                ..Default::default()
+            },
+            color: UiColor(Color::NONE),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            // only the first sections are localized, the values follow them
+            parent
+                .spawn_bundle(TextBundle::from_sections([
+                    TextSection::new("Score", text_style.clone()),
+                    TextSection::new("", text_style.clone()),
+                ]))
+                .insert_bundle((ScoreText, ScaledFont(32.0), Localized("Score")));
+
+            parent
+                .spawn_bundle(TextBundle::from_sections([
+                    TextSection::new("Time", text_style.clone()),
+                    TextSection::new("", text_style.clone()),
+                ]))
+                .insert_bundle((ClockText, ScaledFont(32.0), Localized("Time")));
+
+            for power_up in PowerUp::ALL {
+                let text_style = TextStyle {
+                    font_size: 28.0,
+                    color: power_up.color(),
+                    ..text_style.clone()
+                };
+
+                parent
+                    .spawn_bundle(TextBundle {
+                        text: Text::from_sections([
+                            TextSection::new(power_up.name(), text_style.clone()),
+                            TextSection::new("", text_style),
+                        ]),
+                        visibility: Visibility { is_visible: false },
+                        ..default()
+                    })
+                    .insert_bundle((EffectText(power_up), ScaledFont(28.0), Localized(power_up.name())));
+            }
+        });
+}
+
+fn update_score_text(score: Option<Res<Score>>, mut query: Query<&mut Text, With<ScoreText>>) {
+    let score = score.map_or(0, |score| score.0);
+
+    query.for_each_mut(|mut text| {
+        let value = format!(": {score}");
+
+        if text.sections[1].value != value {
+            text.sections[1].value = value;
+        }
+    });
+}
+
+fn update_clock_text(clock: Option<Res<ModeClock>>, mut query: Query<(&mut Text, &mut Visibility), With<ClockText>>) {
+    query.for_each_mut(|(mut text, mut visibility)| {
+        if visibility.is_visible != clock.is_some() {
+            visibility.is_visible = clock.is_some();
+        }
+
+        if let Some(clock) = &clock {
+            let value = format!(": {:.0}", clock.duration().saturating_sub(clock.elapsed()).as_secs_f32().ceil());
+
+            if text.sections[1].value != value {
+                text.sections[1].value = value;
+            }
+        }
+    });
+}
+
+/// Shows the `T` effect lasting the longest among the snakes, along with its level if it has several.
+fn update_effect_text<T: Effect>(
+    effect_query: Query<&TimedEffect<T>>,
+    mut text_query: Query<(&mut Text, &mut Visibility, &EffectText)>,
+) {
+    let effect = effect_query
+        .iter()
+        .max_by(|a, b| a.remaining().total_cmp(&b.remaining()));
+
+    text_query.for_each_mut(|(mut text, mut visibility, effect_text)| {
+        if effect_text.0 != T::POWER_UP {
+            return;
+        }
+
+        if visibility.is_visible != effect.is_some() {
+            visibility.is_visible = effect.is_some();
+        }
+
+        if let Some(effect) = effect {
+            // the score multiplier is the only effect with several levels, the first one doubling the points
+            let level = match T::POWER_UP.stacking() {
+                Stacking::Level(max) if max > 1 => format!(" x{}", effect.level() + 1),
+                Stacking::Level(_) | Stacking::Duration(_) => String::new(),
+            };
+            let value = format!("{level}: {:.0}s", effect.remaining().ceil());
+
+            if text.sections[1].value != value {
+                text.sections[1].value = value;
+            }
+        }
+    });
+}
@@ -1,106 +1,152 @@
-use std::num::NonZeroUsize;
+use std::marker::PhantomData;
+use std::time::Duration;
 
 use bevy::app::AppExit;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use iyes_loopless::prelude::*;
 use leafwing_input_manager::prelude::*;
 
+use super::{despawn_screen_entities, reset_main_camera, MainCamera};
+use super::tutorial::Tutorial;
 use crate::assets::TextureAssets;
-use crate::game::apple::AppleSpawner;
-use crate::game::bush::BushBuilder;
-use crate::game::components::{Direction, Lifetime, NumberGenerator, Position};
-use crate::game::snake::{SnakeAction, SnakeEvent};
-use crate::game::tile_map::{self, TileMap, TileMapOptions, TileMapPosition};
-use crate::game::wind::WindTimer;
-use crate::game::{bush, snake, GamePlugin};
-use crate::states::AppScreen;
+use crate::cli::LaunchOptions;
+use crate::game::animation::GameOver;
+use crate::game::apple::{Apple, AppleSequence, AppleSpawner};
+use crate::game::bush::BushParameters;
+use crate::game::camera::{CameraTarget, GameCamera};
+use crate::game::campaign::CampaignRun;
+use crate::game::components::{Lifetime, NumberGenerator, Position, RigidBody};
+use crate::game::daily::DailyRun;
+use crate::game::input_controller::Controller;
+use crate::game::level::{self, ActiveLevel, CurrentLevel, Level, LevelEntity};
+use crate::game::play_mode::{ModeClock, PlayMode, Ranked};
+use crate::game::power_up::{PowerUpItem, PowerUpSpawner, SlowMotion, TimedEffect, SLOW_MOTION_FACTOR};
+use crate::game::rewind::RewindBuffer;
+use crate::game::rng::GameRng;
+use crate::game::save::{self, PendingLoad, SaveRequest};
+use crate::game::score::Score;
+use crate::game::snake::{MoveTimer, Snake, SnakeAction, SnakeEvent};
+use crate::game::tile_map::{TileMap, TileMapOptions, TileMapPosition, TILE_SIZE};
+use crate::game::wind::{WindParameters, WindTimer};
+use crate::game::{snake, GamePlugin};
+use crate::settings::{Settings, SnakeReload};
+use crate::skin::{Skin, SkinCatalog};
+use crate::states::{AppScreen, GameMode, GameStatus};
 use crate::systems;
 
-#[derive(Component)]
-struct Player;
+/// Level being played from the editor instead of the current one.
+pub struct Playtest(pub Handle<Level>);
 
 pub struct InGamePlugin;
 
 impl Plugin for InGamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(GamePlugin)
+        app.add_loopless_state(GameStatus::Running)
+            .add_plugin(GamePlugin)
+            .init_resource::<CurrentLevel>()
+            .init_resource::<GameMode>()
             .add_enter_system(AppScreen::InGame, setup_game)
+            .add_enter_system(AppScreen::InGame, take_main_camera)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppScreen::InGame)
+                    .with_system(reload_level)
+                    .with_system(
+                        spawn_level
+                            .run_unless_resource_exists::<TileMap>()
+                            .run_unless_resource_exists::<PendingLoad>(),
+                    )
+                    .with_system(setup_player)
+                    .with_system(apply_snake_speed)
+                    .with_system(quick_save)
+                    .with_system(leave_playtest.run_if_resource_exists::<Playtest>())
+                    .with_system(end_game)
+                    .into(),
+            )
             .add_exit_system_set(
                 AppScreen::InGame,
                 SystemSet::new()
-                    .with_system(systems::despawn_all)
+                    .with_system(despawn_screen_entities)
+                    .with_system(reset_game_status)
+                    .with_system(systems::remove_component::<GameCamera>)
+                    .with_system(reset_main_camera)
                     .with_system(systems::despawn_resource::<TileMap>)
-                    .with_system(systems::despawn_resource::<AppleSpawner>),
+                    .with_system(systems::despawn_resource::<AppleSpawner>)
+                    .with_system(systems::despawn_resource::<AppleSequence>)
+                    .with_system(systems::despawn_resource::<PowerUpSpawner>)
+                    .with_system(systems::despawn_resource::<WindTimer>)
+                    .with_system(systems::despawn_resource::<WindParameters>)
+                    .with_system(systems::despawn_resource::<BushParameters>)
+                    .with_system(systems::despawn_resource::<Score>)
+                    .with_system(systems::despawn_resource::<RewindBuffer>)
+                    .with_system(systems::despawn_resource::<ModeClock>)
+                    .with_system(systems::despawn_resource::<Ranked>)
+                    .with_system(systems::despawn_resource::<ActiveLevel>),
             );
     }
 }
 
-fn setup_game(mut commands: Commands, textures: Res<TextureAssets>) {
-    commands.spawn_bundle(Camera2dBundle {
-        projection: OrthographicProjection {
-            scale: 1.5,
-            ..default()
-        },
-        ..default()
+fn leave_playtest(mut commands: Commands, keys: Res<Input<KeyCode>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        commands.remove_resource::<Playtest>();
+        commands.insert_resource(NextState(AppScreen::Editor));
+    }
+}
+
+/// Shows the game over overlay once every snake is done dying, or goes back to the editor when playtesting.
+fn end_game(mut commands: Commands, mut game_over_reader: EventReader<GameOver>, playtest: Option<Res<Playtest>>) {
+    if game_over_reader.iter().last().is_none() {
+        return;
+    }
+
+    if playtest.is_some() {
+        commands.remove_resource::<Playtest>();
+        commands.insert_resource(NextState(AppScreen::Editor));
+    } else {
+        commands.insert_resource(NextState(GameStatus::GameOver));
+    }
+}
+
+/// Leaves the game status behind along with the game, so that the next game starts running.
+fn reset_game_status(mut commands: Commands) {
+    commands.insert_resource(NextState(GameStatus::Running));
+}
+
+/// Lets the [`GameCamera`] frame the tile map with the main camera.
+fn take_main_camera(mut commands: Commands, camera_query: Query<Entity, With<MainCamera>>) {
+    camera_query.for_each(|entity| {
+        commands.entity(entity).insert(GameCamera::default());
     });
+}
 
-    let tile_map = TileMap::empty(16, 9);
-
-    let entity = snake::spawn_snake(
-        &mut commands,
-        Direction::Down,
-        IVec3::new(4, 5, 1),
-        NonZeroUsize::new(4).unwrap(),
-        NumberGenerator::from_range(0..7),
-    );
-
-    for (x, y, angle) in [(5, 5, 50.0), (12, 8, 0.0), (1, 0, 170.0), (2, 4, 234.0)] {
-        BushBuilder::default()
-            .with_position(IVec3::new(x, y, 1))
-            .with_angle(angle)
-            // .animate()
-            .spawn(&mut commands, &textures);
+fn setup_game(
+    mut commands: Commands,
+    options: Res<LaunchOptions>,
+    settings: Res<Settings>,
+    tutorial: Option<Res<Tutorial>>,
+    campaign_run: Option<Res<CampaignRun>>,
+    daily_run: Option<Res<DailyRun>>,
+    playtest: Option<Res<Playtest>>,
+) {
+    if tutorial.is_some() {
+        commands.insert_resource(NextState(GameStatus::Tutorial));
     }
 
-    commands.insert_resource(WindTimer::new(5.0..10.0));
-
-    commands
-        .entity(entity)
-        .insert_bundle(InputManagerBundle::<SnakeAction> {
-            input_map: InputMap::new([
-                (KeyCode::Up, SnakeAction::MoveUp),
-                (KeyCode::Left, SnakeAction::MoveLeft),
-                (KeyCode::Right, SnakeAction::MoveRight),
-                (KeyCode::Down, SnakeAction::MoveDown),
-            ]),
-            ..Default::default()
-        })
-        .insert(Player);
-
-    commands
-        .spawn()
-        .insert_bundle(TransformBundle::default())
-        .insert_bundle(VisibilityBundle::default())
-        .insert(Name::new("Tile Map"))
-        .with_children(|parent| {
-            tile_map.tiles().for_each(|tile| {
-                parent
-                    .spawn_bundle(SpriteBundle {
-                        sprite: Sprite {
-                            color: Color::rgb(0.5, 0.9, 0.19),
-                            custom_size: Some(Vec2::splat(tile_map::TILE_SIZE)),
-                            ..default()
-                        },
-                        ..default()
-                    })
-                    .insert(Position(IVec3::new(tile.x() as i32, tile.y() as i32, 0)))
-                    .insert(Name::new(format!("Tile ({}, {})", tile.x(), tile.y())));
-            });
-        });
+    // the tutorial, the campaign, the daily challenge and playtests follow the rules of an endless game, and are not
+    // ranked, no more than practice games
+    if tutorial.is_none() && campaign_run.is_none() && daily_run.is_none() && playtest.is_none() {
+        commands.insert_resource(settings.play_mode);
+
+        if !settings.practice_mode {
+            commands.insert_resource(Ranked);
+        }
+    } else {
+        commands.insert_resource(PlayMode::Endless);
+    }
 
-    commands.insert_resource(tile_map);
     commands.insert_resource(TileMapOptions {
-        tile_size: 128.0,
+        tile_size: TILE_SIZE,
         position: TileMapPosition::Centered,
     });
 
@@ -108,5 +154,239 @@ fn setup_game(mut commands: Commands, textures: Res<TextureAssets>) {
         timer: Timer::from_seconds(2.0, true),
         max_apples: 3,
         apple_lifetime: Some(Lifetime::from_seconds(5.0)),
+        golden_apple_chance: 0.1,
+    });
+
+    // the tutorial sticks to the basics, and the daily challenge is the same board for everyone, without random
+    // power-ups
+    if tutorial.is_none() && daily_run.is_none() {
+        commands.insert_resource(PowerUpSpawner {
+            timer: Timer::from_seconds(12.0, true),
+            max_power_ups: 1,
+            power_up_lifetime: Lifetime::from_seconds(6.0),
+        });
+    }
+
+    // the apples of the daily challenge come from their own sequence, the same for every player whatever happens on
+    // the board
+    if let Some(daily_run) = &daily_run {
+        commands.insert_resource(daily_run.apple_sequence());
+    }
+
+    let seed = daily_run.map(|daily_run| u64::from(daily_run.seed)).or(options.seed);
+
+    commands.insert_resource(seed.map_or_else(GameRng::from_entropy, GameRng::from_seed));
+    commands.insert_resource(Score::default());
+    commands.insert_resource(RewindBuffer::default());
+}
+
+/// Level being played, a playtest, the tutorial, a campaign level or the daily challenge taking the place of the
+/// current level.
+#[derive(SystemParam)]
+struct PlayedLevel<'w, 's> {
+    levels: Res<'w, Assets<Level>>,
+    current_level: Res<'w, CurrentLevel>,
+    playtest: Option<Res<'w, Playtest>>,
+    tutorial: Option<Res<'w, Tutorial>>,
+    campaign_run: Option<Res<'w, CampaignRun>>,
+    daily_run: Option<Res<'w, DailyRun>>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl PlayedLevel<'_, '_> {
+    fn handle(&self) -> &Handle<Level> {
+        self.playtest
+            .as_ref()
+            .map(|playtest| &playtest.0)
+            .or_else(|| self.tutorial.as_ref().map(|tutorial| &tutorial.level))
+            .or_else(|| self.campaign_run.as_ref().map(|campaign_run| &campaign_run.level))
+            .or_else(|| self.daily_run.as_ref().map(|daily_run| &daily_run.level))
+            .unwrap_or(&self.current_level.0)
+    }
+
+    /// Returns the level once loaded.
+    fn get(&self) -> Option<&Level> {
+        self.levels.get(self.handle())
+    }
+}
+
+/// Spawns the snakes of the players at the spawn of a level, with the skin chosen in the settings.
+#[derive(SystemParam)]
+struct SnakeSpawner<'w, 's> {
+    settings: Res<'w, Settings>,
+    skin_catalog: Res<'w, SkinCatalog>,
+    skins: Res<'w, Assets<Skin>>,
+    options: Res<'w, LaunchOptions>,
+    mode: Res<'w, GameMode>,
+    snake_query: Query<'w, 's, (), With<Snake>>,
+}
+
+impl SnakeSpawner<'_, '_> {
+    /// Spawns the snakes, unless they survived a reload of the level.
+    fn spawn(&self, commands: &mut Commands, level: &Level) {
+        if !self.snake_query.is_empty() {
+            return;
+        }
+
+        let snake_spawn = level.snake_spawn();
+        let variant_range = self
+            .skin_catalog
+            .find(&self.settings.skin, &self.skins)
+            .map_or(0..1, Skin::variant_range);
+
+        let position = snake_spawn.position();
+        let mut spawns = vec![(snake_spawn.direction, position)];
+
+        // the second player starts from the opposite side of the map, heading the other way
+        if *self.mode == GameMode::MultiPlayer {
+            let mirrored_position = IVec3::new(
+                level.width as i32 - 1 - position.x,
+                level.height as i32 - 1 - position.y,
+                position.z,
+            );
+
+            spawns.push((snake_spawn.direction.opposite(), mirrored_position));
+        }
+
+        for (i, (direction, position)) in spawns.into_iter().enumerate() {
+            let mut number_generator = NumberGenerator::from_range(variant_range.clone());
+
+            if let Some(seed) = self.options.seed {
+                number_generator = number_generator.with_seed(seed.wrapping_add(i as u64));
+            }
+
+            snake::spawn_snake(commands, direction, position, snake_spawn.length, number_generator);
+        }
+    }
+}
+
+/// Spawns the level as soon as it is loaded, along with the snakes if there are none yet.
+fn spawn_level(
+    mut commands: Commands,
+    textures: Res<TextureAssets>,
+    played_level: PlayedLevel,
+    snake_spawner: SnakeSpawner,
+) {
+    let Some(level) = played_level.get() else {
+        return;
+    };
+
+    let tile_map = level::spawn_level(&mut commands, &textures, level);
+
+    level::insert_parameters(&mut commands, level);
+    commands.insert_resource(ActiveLevel(level.clone()));
+
+    snake_spawner.spawn(&mut commands, level);
+
+    commands.insert_resource(tile_map);
+}
+
+/// Hands the control of the snakes to the players as soon as they are spawned, from the level or from a saved game.
+fn setup_player(mut commands: Commands, query: Query<Entity, Added<Snake>>, controller_query: Query<&Controller>) {
+    let player_count = controller_query.iter().count();
+
+    query.iter().enumerate().for_each(|(i, entity)| {
+        commands
+            .entity(entity)
+            .insert_bundle(InputManagerBundle::<SnakeAction>::default())
+            .insert_bundle((Controller(player_count + i), CameraTarget));
+    });
+}
+
+/// Paces the snakes according to the speed chosen in the settings, adapted to the mode and to slow motion.
+fn apply_snake_speed(
+    settings: Res<Settings>,
+    mode: Res<PlayMode>,
+    mut query: Query<(&mut MoveTimer, Option<&TimedEffect<SlowMotion>>)>,
+) {
+    let interval = settings.snake_speed.move_interval() * mode.move_interval_factor();
+
+    query.for_each_mut(|(mut timer, slow_motion)| {
+        let factor = if slow_motion.is_some() { SLOW_MOTION_FACTOR } else { 1.0 };
+        let interval = Duration::from_secs_f32(interval * factor);
+
+        if timer.duration() != interval {
+            timer.set_duration(interval);
+        }
     });
 }
+
+fn quick_save(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    daily_run: Option<Res<DailyRun>>,
+    mut save_writer: EventWriter<SaveRequest>,
+) {
+    if !DailyRun::allows_retries(daily_run.as_deref()) {
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::F5) {
+        save_writer.send(SaveRequest(save::QUICK_SAVE_FILE.to_string()));
+    } else if keys.just_pressed(KeyCode::F9) {
+        commands.insert_resource(PendingLoad(save::QUICK_SAVE_FILE.to_string()));
+    }
+}
+
+/// Entities torn down or kept when the level is reloaded.
+#[derive(SystemParam)]
+struct ReloadedEntities<'w, 's> {
+    level_entities: Query<'w, 's, Entity, Or<(With<LevelEntity>, With<Apple>, With<PowerUpItem>)>>,
+    snakes: Query<'w, 's, (Entity, &'static Snake)>,
+    positions: Query<
+        'w,
+        's,
+        &'static mut Position,
+        (With<RigidBody>, Without<LevelEntity>, Without<Apple>, Without<PowerUpItem>),
+    >,
+}
+
+/// Tears down the level when its file changes on disk so that [`spawn_level`] rebuilds it.
+fn reload_level(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<Level>>,
+    played_level: PlayedLevel,
+    settings: Res<Settings>,
+    mut rewind_buffer: ResMut<RewindBuffer>,
+    mut entities: ReloadedEntities,
+) {
+    let handle = played_level.handle();
+    let is_modified = events
+        .iter()
+        .any(|event| matches!(event, AssetEvent::Modified { handle: modified } if modified == handle));
+
+    if !is_modified {
+        return;
+    }
+
+    info!("level modified, reloading it");
+
+    entities
+        .level_entities
+        .for_each(|entity| commands.entity(entity).despawn_recursive());
+
+    match settings.level_reload {
+        SnakeReload::Keep => {
+            // let the new tile map know about the entities that survive the reload
+            entities
+                .positions
+                .for_each_mut(|mut position| position.set_changed());
+        }
+        SnakeReload::Reset => {
+            // snapshots refer to the snake about to be despawned
+            rewind_buffer.clear();
+
+            entities.snakes.for_each(|(entity, snake)| {
+                snake
+                    .fragments
+                    .iter()
+                    .for_each(|fragment| commands.entity(*fragment).despawn_recursive());
+
+                commands.entity(entity).despawn_recursive();
+            });
+        }
+    }
+
+    commands.remove_resource::<TileMap>();
+}
@@ -1,10 +1,22 @@
 use bevy::app::AppExit;
+use bevy::ecs::system::EntityCommands;
 use bevy::prelude::*;
 use iyes_loopless::prelude::*;
 
-use crate::states::AppScreen;
+use super::despawn_screen_entities;
+use super::settings::next;
+use super::tutorial::Tutorial;
 use crate::assets::FontAssets;
-use crate::systems;
+use crate::audio::SoundEffect;
+use crate::display::{ScaledFont, ScaledSize};
+use crate::game::daily::{ChallengeDate, DailyRun};
+use crate::game::level::Level;
+use crate::game::play_mode::PlayMode;
+use crate::game::save::{self, PendingLoad};
+use crate::i18n::Localized;
+use crate::settings::Settings;
+use crate::states::AppScreen;
+use crate::storage;
 
 #[derive(Component)]
 struct ButtonAction(Box<dyn Fn(&mut Commands) + Send + Sync + 'static>);
@@ -12,14 +24,36 @@ struct ButtonAction(Box<dyn Fn(&mut Commands) + Send + Sync + 'static>);
 #[derive(Component)]
 struct PlayButton;
 
+#[derive(Component)]
+struct ContinueButton;
+
+/// Cycles through the modes of the games started with the play button.
+#[derive(Component)]
+struct ModeButton;
+
+#[derive(Component)]
+struct CampaignButton;
+
+#[derive(Component)]
+struct DailyButton;
+
+#[derive(Component)]
+struct TutorialButton;
+
+#[derive(Component)]
+struct EditorButton;
+
+#[derive(Component)]
+struct SettingsButton;
+
 #[derive(Component)]
 struct ExitButton;
 
 #[derive(Component)]
-struct ButtonHover(Color);
+pub struct ButtonHover(pub Color);
 
 #[derive(Component)]
-struct ButtonNormal(Color);
+pub struct ButtonNormal(pub Color);
 
 pub struct MainMenuPlugin;
 
@@ -30,22 +64,131 @@ impl Plugin for MainMenuPlugin {
                 ConditionSet::new()
                     .run_in_state(AppScreen::MainMenu)
                     .with_system(start_game)
+                    .with_system(change_mode)
+                    .with_system(update_mode_text)
+                    .with_system(open_level_select)
+                    .with_system(start_daily_challenge)
+                    .with_system(start_tutorial)
+                    .with_system(continue_game)
+                    .with_system(open_editor)
+                    .with_system(open_settings)
                     .with_system(exit_main_menu)
                     .with_system(button_interaction)
                     .into(),
             )
-            .add_exit_system(AppScreen::MainMenu, systems::despawn_all);
+            .add_exit_system(AppScreen::MainMenu, despawn_screen_entities);
     }
 }
 
-fn start_game(mut commands: Commands, query: Query<&Interaction, (Changed<Interaction>, With<PlayButton>)>) {
+/// Starts a game, preceded by the tutorial until it is completed once.
+fn start_game(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    query: Query<&Interaction, (Changed<Interaction>, With<PlayButton>)>,
+) {
     query.for_each(|interaction| {
         if matches!(*interaction, Interaction::Clicked) {
+            if !settings.tutorial_completed {
+                commands.insert_resource(Tutorial::new(&asset_server));
+            }
+
             commands.insert_resource(NextState(AppScreen::InGame));
         }
     });
 }
 
+fn change_mode(mut settings: ResMut<Settings>, query: Query<&Interaction, (Changed<Interaction>, With<ModeButton>)>) {
+    query.for_each(|interaction| {
+        if matches!(*interaction, Interaction::Clicked) {
+            settings.play_mode = next(&PlayMode::ALL, settings.play_mode);
+        }
+    });
+}
+
+/// Shows the mode after the localized label of the mode button.
+fn update_mode_text(
+    settings: Res<Settings>,
+    button_query: Query<&Children, With<ModeButton>>,
+    mut text_query: Query<&mut Text>,
+) {
+    let mut iter = text_query.iter_many_mut(button_query.iter().flatten());
+
+    while let Some(mut text) = iter.fetch_next() {
+        let value = format!(": {}", settings.language.translate(settings.play_mode.name()));
+
+        match text.sections.get_mut(1) {
+            Some(section) if section.value == value => (),
+            Some(section) => section.value = value,
+            None => {
+                let style = text.sections[0].style.clone();
+
+                text.sections.push(TextSection::new(value, style));
+            }
+        }
+    }
+}
+
+fn open_level_select(mut commands: Commands, query: Query<&Interaction, (Changed<Interaction>, With<CampaignButton>)>) {
+    query.for_each(|interaction| {
+        if matches!(*interaction, Interaction::Clicked) {
+            commands.insert_resource(NextState(AppScreen::LevelSelect));
+        }
+    });
+}
+
+/// Starts the challenge of the day, scored the first time it is played.
+fn start_daily_challenge(
+    mut commands: Commands,
+    mut levels: ResMut<Assets<Level>>,
+    query: Query<&Interaction, (Changed<Interaction>, With<DailyButton>)>,
+) {
+    query.for_each(|interaction| {
+        if matches!(*interaction, Interaction::Clicked) {
+            commands.insert_resource(DailyRun::new(&mut levels, ChallengeDate::today()));
+            commands.insert_resource(NextState(AppScreen::InGame));
+        }
+    });
+}
+
+fn start_tutorial(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    query: Query<&Interaction, (Changed<Interaction>, With<TutorialButton>)>,
+) {
+    query.for_each(|interaction| {
+        if matches!(*interaction, Interaction::Clicked) {
+            commands.insert_resource(Tutorial::new(&asset_server));
+            commands.insert_resource(NextState(AppScreen::InGame));
+        }
+    });
+}
+
+fn continue_game(mut commands: Commands, query: Query<&Interaction, (Changed<Interaction>, With<ContinueButton>)>) {
+    query.for_each(|interaction| {
+        if matches!(*interaction, Interaction::Clicked) {
+            commands.insert_resource(PendingLoad(save::QUICK_SAVE_FILE.to_string()));
+            commands.insert_resource(NextState(AppScreen::InGame));
+        }
+    });
+}
+
+fn open_editor(mut commands: Commands, query: Query<&Interaction, (Changed<Interaction>, With<EditorButton>)>) {
+    query.for_each(|interaction| {
+        if matches!(*interaction, Interaction::Clicked) {
+            commands.insert_resource(NextState(AppScreen::Editor));
+        }
+    });
+}
+
+fn open_settings(mut commands: Commands, query: Query<&Interaction, (Changed<Interaction>, With<SettingsButton>)>) {
+    query.for_each(|interaction| {
+        if matches!(*interaction, Interaction::Clicked) {
+            commands.insert_resource(NextState(AppScreen::Settings));
+        }
+    });
+}
+
 fn exit_main_menu(
     mut exit_writer: EventWriter<AppExit>,
     query: Query<&Interaction, (Changed<Interaction>, With<ExitButton>)>,
@@ -58,8 +201,9 @@ fn exit_main_menu(
     }
 }
 
-fn button_interaction(
+pub fn button_interaction(
     mut query: Query<(&mut UiColor, &Interaction, &ButtonNormal, &ButtonHover), (Changed<Interaction>, With<Button>)>,
+    mut sound_writer: EventWriter<SoundEffect>,
 ) {
     query.for_each_mut(|(mut color, interaction, button_normal, button_hover)| {
         match *interaction {
@@ -68,15 +212,14 @@ fn button_interaction(
             }
             Interaction::Hovered => {
                 *color = button_hover.0.into();
+                sound_writer.send(SoundEffect::MenuMove);
             }
-            Interaction::Clicked => (),
+            Interaction::Clicked => sound_writer.send(SoundEffect::MenuSelect),
         }
     });
 }
 
 fn setup_main_menu(mut commands: Commands, fonts: Res<FontAssets>) {
-    commands.spawn_bundle(Camera2dBundle::default());
-
     commands
         .spawn_bundle(NodeBundle {
             style: Style {
@@ -91,60 +234,57 @@ fn setup_main_menu(mut commands: Commands, fonts: Res<FontAssets>) {
             ..Default::default()
         })
         .with_children(|parent| {
-            parent
-                .spawn_bundle(ButtonBundle {
-                    style: Style {
-                        size: Size::new(Val::Px(150.0), Val::Px(65.0)),
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        margin: UiRect {
-                            bottom: Val::Px(20.0),
-                            ..default()
-                        },
-                        ..default()
-                    },
-                    ..default()
-                })
-                .with_children(|parent| {
-                    parent.spawn_bundle(TextBundle::from_section(
-                        "Play",
-                        TextStyle {
-                            font: fonts.text.clone(),
-                            font_size: 40.0,
-                            color: Color::rgb(0.9, 0.9, 0.9),
-                        },
-                    ));
-                })
-                .insert(PlayButton)
-                .insert(ButtonNormal(Color::rgb(0.1, 0.1, 0.1)))
-                .insert(ButtonHover(Color::rgb(0.2, 0.2, 0.2)));
+            if storage::path(save::QUICK_SAVE_FILE).exists() {
+                spawn_button(parent, &fonts, "Continue").insert(ContinueButton);
+            }
+
+            spawn_button(parent, &fonts, "Play").insert(PlayButton);
+            spawn_button(parent, &fonts, "Mode").insert_bundle((ModeButton, ScaledSize(Vec2::new(500.0, 65.0))));
+            spawn_button(parent, &fonts, "Campaign").insert(CampaignButton);
+            spawn_button(parent, &fonts, "Daily challenge")
+                .insert_bundle((DailyButton, ScaledSize(Vec2::new(400.0, 65.0))));
+            spawn_button(parent, &fonts, "Tutorial").insert(TutorialButton);
+            spawn_button(parent, &fonts, "Editor").insert(EditorButton);
+            spawn_button(parent, &fonts, "Settings").insert(SettingsButton);
+            spawn_button(parent, &fonts, "Exit").insert(ExitButton);
+        });
+}
 
+pub fn spawn_button<'w, 's, 'a>(
+    parent: &'a mut ChildBuilder<'w, 's, '_>,
+    fonts: &FontAssets,
+    label: &'static str,
+) -> EntityCommands<'w, 's, 'a> {
+    let mut button = parent.spawn_bundle(ButtonBundle {
+        style: Style {
+            size: Size::new(Val::Px(250.0), Val::Px(65.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            margin: UiRect {
+                bottom: Val::Px(20.0),
+                ..default()
+            },
+            ..default()
+        },
+        ..default()
+    });
+
+    button
+        .with_children(|parent| {
             parent
-                .spawn_bundle(ButtonBundle {
-                    style: Style {
-                        size: Size::new(Val::Px(150.0), Val::Px(65.0)),
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        margin: UiRect {
-                            bottom: Val::Px(20.0),
-                            ..default()
-                        },
-                        ..default()
+                .spawn_bundle(TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font: fonts.text.clone(),
+                        font_size: 40.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
                     },
-                    ..default()
-                })
-                .with_children(|parent| {
-                    parent.spawn_bundle(TextBundle::from_section(
-                        "Exit",
-                        TextStyle {
-                            font: fonts.text.clone(),
-                            font_size: 40.0,
-                            color: Color::rgb(0.9, 0.9, 0.9),
-                        },
-                    ));
-                })
-                .insert(ExitButton)
-                .insert(ButtonNormal(Color::rgb(0.1, 0.1, 0.1)))
-                .insert(ButtonHover(Color::rgb(0.2, 0.2, 0.2)));
-        });
+                ))
+                .insert_bundle((ScaledFont(40.0), Localized(label)));
+        })
+        .insert(ScaledSize(Vec2::new(250.0, 65.0)))
+        .insert(ButtonNormal(Color::rgb(0.1, 0.1, 0.1)))
+        .insert(ButtonHover(Color::rgb(0.2, 0.2, 0.2)));
+
+    button
 }
@@ -1,28 +1,110 @@
+pub mod campaign;
+pub mod controls;
+pub mod editor;
+pub mod game_over;
+pub mod hud;
 pub mod in_game;
 pub mod main_menu;
+pub mod settings;
 pub mod splash;
+pub mod tutorial;
 
 use bevy::prelude::*;
 use bevy_tweening::TweeningPlugin;
 use iyes_loopless::prelude::*;
 
-use crate::assets::{FontAssets, TextureAssets};
+use crate::assets::{AudioAssets, FontAssets, TextureAssets};
 use crate::states::AppScreen;
 
+use campaign::CampaignScreenPlugin;
+use controls::ControlsScreenPlugin;
+use editor::EditorPlugin;
+use game_over::GameOverPlugin;
+use hud::HudPlugin;
 use in_game::InGamePlugin;
 use main_menu::MainMenuPlugin;
+use settings::SettingsScreenPlugin;
 use splash::SplashPlugin;
+use tutorial::TutorialPlugin;
+
+/// Ties an entity to the screen it was spawned in, so that it is despawned, along with its children, when leaving
+/// that screen.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Component)]
+pub struct ScreenEntity(pub AppScreen);
+
+/// Keeps a top-level entity alive across screens, like the main camera or the background music.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Component)]
+pub struct Persistent;
+
+/// Camera shared by every screen, the game and the editor taking control of it while they are shown.
+#[derive(Debug, Component)]
+pub struct MainCamera;
 
 pub struct ScreensPlugin;
 
 impl Plugin for ScreensPlugin {
     fn build(&self, app: &mut App) {
-        app.add_loopless_state(AppScreen::InGame)
+        app.add_loopless_state(AppScreen::Splash)
             .init_resource::<FontAssets>()
             .init_resource::<TextureAssets>()
+            .init_resource::<AudioAssets>()
             .add_plugin(TweeningPlugin)
             .add_plugin(SplashPlugin)
             .add_plugin(MainMenuPlugin)
-            .add_plugin(InGamePlugin);
+            .add_plugin(InGamePlugin)
+            .add_plugin(GameOverPlugin)
+            .add_plugin(HudPlugin)
+            .add_plugin(TutorialPlugin)
+            .add_plugin(CampaignScreenPlugin)
+            .add_plugin(EditorPlugin)
+            .add_plugin(SettingsScreenPlugin)
+            .add_plugin(ControlsScreenPlugin)
+            .add_startup_system(spawn_main_camera)
+            .add_system_to_stage(CoreStage::Last, scope_to_screen);
     }
 }
+
+fn spawn_main_camera(mut commands: Commands) {
+    commands
+        .spawn_bundle(Camera2dBundle::default())
+        .insert(MainCamera)
+        .insert(Persistent);
+}
+
+/// Gives the main camera back its default framing once a screen that moved or zoomed it is left.
+pub fn reset_main_camera(mut query: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>) {
+    let Camera2dBundle {
+        transform: default_transform,
+        projection: default_projection,
+        ..
+    } = Camera2dBundle::default();
+
+    query.for_each_mut(|(mut transform, mut projection)| {
+        *transform = default_transform;
+        projection.scale = default_projection.scale;
+    });
+}
+
+/// Ties the top-level entities spawned during the frame to the current screen, unless they are [`Persistent`].
+fn scope_to_screen(
+    mut commands: Commands,
+    screen: Res<CurrentState<AppScreen>>,
+    query: Query<Entity, (Without<ScreenEntity>, Without<Persistent>, Without<Parent>)>,
+) {
+    query.for_each(|entity| {
+        commands.entity(entity).insert(ScreenEntity(screen.0));
+    });
+}
+
+/// Despawns the entities of the screen being left, leaving the other ones untouched.
+pub fn despawn_screen_entities(
+    mut commands: Commands,
+    screen: Res<CurrentState<AppScreen>>,
+    query: Query<(Entity, &ScreenEntity)>,
+) {
+    query.for_each(|(entity, screen_entity)| {
+        if screen_entity.0 == screen.0 {
+            commands.entity(entity).despawn_recursive();
+        }
+    });
+}
@@ -0,0 +1,249 @@
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use super::despawn_screen_entities;
+use super::main_menu::{self, ButtonHover, ButtonNormal};
+use crate::assets::FontAssets;
+use crate::display::{ScaledFont, ScaledSize};
+use crate::i18n::Language;
+use crate::settings::{ColorPalette, DisplayMode, Settings, SnakeReload, SnakeSpeed};
+use crate::skin::{Skin, SkinCatalog};
+use crate::states::AppScreen;
+
+/// Option changed by clicking a button of the settings screen.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Component)]
+enum SettingsOption {
+    MasterVolume,
+    MusicVolume,
+    EffectsVolume,
+    SnakeSpeed,
+    Controls,
+    Window,
+    Palette,
+    Skin,
+    Language,
+    LevelReload,
+    PracticeMode,
+    Back,
+}
+
+impl SettingsOption {
+    const ALL: [Self; 12] = [
+        Self::MasterVolume,
+        Self::MusicVolume,
+        Self::EffectsVolume,
+        Self::SnakeSpeed,
+        Self::Controls,
+        Self::Window,
+        Self::Palette,
+        Self::Skin,
+        Self::Language,
+        Self::LevelReload,
+        Self::PracticeMode,
+        Self::Back,
+    ];
+
+    const fn label(self) -> &'static str {
+        match self {
+            Self::MasterVolume => "Master volume",
+            Self::MusicVolume => "Music volume",
+            Self::EffectsVolume => "Effects volume",
+            Self::SnakeSpeed => "Snake speed",
+            Self::Controls => "Controls",
+            Self::Window => "Window",
+            Self::Palette => "Colors",
+            Self::Skin => "Skin",
+            Self::Language => "Language",
+            Self::LevelReload => "Level reload",
+            Self::PracticeMode => "Practice mode",
+            Self::Back => "Back",
+        }
+    }
+
+    fn text(self, settings: &Settings, skins: &[&Skin]) -> String {
+        let language = settings.language;
+        let label = language.translate(self.label());
+
+        let value = match self {
+            Self::MasterVolume => format!("{:.0}%", settings.audio.master * 100.0),
+            Self::MusicVolume => format!("{:.0}%", settings.audio.music * 100.0),
+            Self::EffectsVolume => format!("{:.0}%", settings.audio.effects * 100.0),
+            Self::SnakeSpeed => language.translate(settings.snake_speed.name()).to_string(),
+            Self::Window => language.translate(settings.window.mode.name()).to_string(),
+            Self::Palette => language.translate(settings.palette.name()).to_string(),
+            Self::Skin => skins
+                .iter()
+                .find(|skin| skin.id == settings.skin)
+                .map_or_else(|| settings.skin.clone(), |skin| skin.name.clone()),
+            Self::Language => settings.language.name().to_string(),
+            Self::LevelReload => language.translate(settings.level_reload.name()).to_string(),
+            Self::PracticeMode => language
+                .translate(if settings.practice_mode { "On" } else { "Off" })
+                .to_string(),
+            Self::Controls | Self::Back => return label.to_string(),
+        };
+
+        format!("{label}: {value}")
+    }
+
+    /// Moves the option to its next value, wrapping around the last one.
+    fn cycle(self, settings: &mut Settings, skins: &[&Skin]) {
+        match self {
+            Self::MasterVolume => settings.audio.master = next_volume(settings.audio.master),
+            Self::MusicVolume => settings.audio.music = next_volume(settings.audio.music),
+            Self::EffectsVolume => settings.audio.effects = next_volume(settings.audio.effects),
+            Self::SnakeSpeed => settings.snake_speed = next(&SnakeSpeed::ALL, settings.snake_speed),
+            Self::Window => settings.window.mode = next(&DisplayMode::ALL, settings.window.mode),
+            Self::Palette => settings.palette = next(&ColorPalette::ALL, settings.palette),
+            Self::Skin => {
+                let ids = skins.iter().map(|skin| skin.id.as_str()).collect::<Vec<_>>();
+
+                if !ids.is_empty() {
+                    settings.skin = next(&ids, settings.skin.as_str()).to_string();
+                }
+            }
+            Self::Language => settings.language = next(&Language::ALL, settings.language),
+            Self::LevelReload => settings.level_reload = next(&SnakeReload::ALL, settings.level_reload),
+            Self::PracticeMode => settings.practice_mode = !settings.practice_mode,
+            Self::Controls | Self::Back => (),
+        }
+    }
+}
+
+pub fn next<T: Copy + PartialEq>(values: &[T], value: T) -> T {
+    let i = values.iter().position(|v| *v == value).unwrap_or_default();
+
+    values[(i + 1) % values.len()]
+}
+
+/// Raises the volume by steps of 10%, going back to silence after the maximum.
+fn next_volume(volume: f32) -> f32 {
+    let step = (volume * 10.0).round().clamp(0.0, 10.0);
+
+    ((step + 1.0) % 11.0) / 10.0
+}
+
+#[derive(Component)]
+struct OptionText(SettingsOption);
+
+pub struct SettingsScreenPlugin;
+
+impl Plugin for SettingsScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_enter_system(AppScreen::Settings, setup_settings)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppScreen::Settings)
+                    .with_system(change_option)
+                    .with_system(update_option_texts)
+                    .with_system(leave_settings)
+                    .with_system(main_menu::button_interaction)
+                    .into(),
+            )
+            .add_exit_system(AppScreen::Settings, despawn_screen_entities);
+    }
+}
+
+fn setup_settings(
+    mut commands: Commands,
+    fonts: Res<FontAssets>,
+    settings: Res<Settings>,
+    skin_catalog: Res<SkinCatalog>,
+    skins: Res<Assets<Skin>>,
+) {
+    let skins = skin_catalog.loaded(&skins);
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            color: UiColor(Color::NONE),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            for option in SettingsOption::ALL {
+                parent
+                    .spawn_bundle(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(700.0), Val::Px(55.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            margin: UiRect {
+                                bottom: Val::Px(12.0),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent
+                            .spawn_bundle(TextBundle::from_section(
+                                option.text(&settings, &skins),
+                                TextStyle {
+                                    font: fonts.text.clone(),
+                                    font_size: 32.0,
+                                    color: Color::rgb(0.9, 0.9, 0.9),
+                                },
+                            ))
+                            .insert_bundle((OptionText(option), ScaledFont(32.0)));
+                    })
+                    .insert_bundle((
+                        option,
+                        ScaledSize(Vec2::new(700.0, 55.0)),
+                        ButtonNormal(Color::rgb(0.1, 0.1, 0.1)),
+                        ButtonHover(Color::rgb(0.2, 0.2, 0.2)),
+                    ));
+            }
+        });
+}
+
+fn change_option(
+    mut commands: Commands,
+    mut settings: ResMut<Settings>,
+    skin_catalog: Res<SkinCatalog>,
+    skins: Res<Assets<Skin>>,
+    query: Query<(&Interaction, &SettingsOption), Changed<Interaction>>,
+) {
+    let skins = skin_catalog.loaded(&skins);
+
+    query.for_each(|(interaction, option)| {
+        if !matches!(*interaction, Interaction::Clicked) {
+            return;
+        }
+
+        match option {
+            SettingsOption::Controls => commands.insert_resource(NextState(AppScreen::Controls)),
+            SettingsOption::Back => commands.insert_resource(NextState(AppScreen::MainMenu)),
+            option => option.cycle(&mut settings, &skins),
+        }
+    });
+}
+
+fn update_option_texts(
+    settings: Res<Settings>,
+    skin_catalog: Res<SkinCatalog>,
+    skins: Res<Assets<Skin>>,
+    mut query: Query<(&mut Text, &OptionText)>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    let skins = skin_catalog.loaded(&skins);
+
+    query.for_each_mut(|(mut text, option_text)| {
+        text.sections[0].value = option_text.0.text(&settings, &skins);
+    });
+}
+
+fn leave_settings(mut commands: Commands, keys: Res<Input<KeyCode>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        commands.insert_resource(NextState(AppScreen::MainMenu));
+    }
+}
@@ -1,26 +1,65 @@
+use std::marker::PhantomData;
+
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use iyes_loopless::prelude::*;
 
-use crate::assets::FontAssets;
+use super::despawn_screen_entities;
+use crate::assets::{AudioAssets, FontAssets, LoadProgress, LoadingAssets, TextureAssets};
+use crate::display::{ScaledFont, ScaledSize};
+use crate::game::campaign::CampaignAssets;
+use crate::skin::SkinCatalog;
 use crate::states::AppScreen;
 use crate::systems;
 
 #[derive(Component, Deref, DerefMut)]
 pub struct SplashTimer(Timer);
 
+#[derive(Component)]
+struct ProgressBar;
+
+#[derive(Component)]
+struct LoadingText;
+
+/// The assets being loaded, along with the server tracking them.
+#[derive(SystemParam)]
+struct Loading<'w, 's> {
+    assets: Res<'w, LoadingAssets>,
+    asset_server: Res<'w, AssetServer>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl Loading<'_, '_> {
+    fn progress(&self) -> LoadProgress {
+        self.assets.progress(&self.asset_server)
+    }
+}
+
 pub struct SplashPlugin;
 
 impl Plugin for SplashPlugin {
     fn build(&self, app: &mut App) {
         app
             .add_enter_system(AppScreen::Splash, setup_splash)
-            .add_system(change_state.run_in_state(AppScreen::Splash).run_if(timer_finished))
-            .add_exit_system(AppScreen::Splash, systems::despawn_all);
+            .add_system(track_loading.run_in_state(AppScreen::Splash))
+            .add_exit_system(AppScreen::Splash, despawn_screen_entities)
+            .add_exit_system(AppScreen::Splash, systems::despawn_resource::<LoadingAssets>);
     }
 }
 
-fn setup_splash(mut commands: Commands, fonts: Res<FontAssets>) {
-    commands.spawn_bundle(Camera2dBundle::default());
+fn setup_splash(
+    mut commands: Commands,
+    fonts: Res<FontAssets>,
+    textures: Res<TextureAssets>,
+    sounds: Res<AudioAssets>,
+    skins: Res<SkinCatalog>,
+    campaign: Res<CampaignAssets>,
+) {
+    let mut loading_assets = LoadingAssets::default();
+    loading_assets.add(&*fonts).add(&*textures).add(&*sounds).add(&*skins).add(&*campaign);
+
+    commands.insert_resource(loading_assets);
 
     commands
         .spawn_bundle(NodeBundle {
@@ -36,36 +75,117 @@ fn setup_splash(mut commands: Commands, fonts: Res<FontAssets>) {
             ..Default::default()
         })
         .with_children(|parent| {
-            parent.spawn_bundle(TextBundle::from_section(
-                "ALP",
-                TextStyle {
-                    font: fonts.text.clone(),
-                    font_size: 200.,
-                    color: Color::WHITE,
-                },
-            ));
-
-            parent.spawn_bundle(TextBundle::from_section(
-                "production",
-                TextStyle {
-                    font: fonts.text.clone(),
-                    font_size: 100.,
-                    color: Color::WHITE,
-                },
-            ));
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    "ALP",
+                    TextStyle {
+                        font: fonts.text.clone(),
+                        font_size: 200.,
+                        color: Color::WHITE,
+                    },
+                ))
+                .insert(ScaledFont(200.));
+
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    "production",
+                    TextStyle {
+                        font: fonts.text.clone(),
+                        font_size: 100.,
+                        color: Color::WHITE,
+                    },
+                ))
+                .insert(ScaledFont(100.));
+
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(600.0), Val::Px(12.0)),
+                        margin: UiRect {
+                            top: Val::Px(40.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    color: UiColor(Color::rgb(0.2, 0.2, 0.2)),
+                    ..default()
+                })
+                .insert(ScaledSize(Vec2::new(600.0, 12.0)))
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                                ..default()
+                            },
+                            color: UiColor(Color::WHITE),
+                            ..default()
+                        })
+                        .insert(ProgressBar);
+                });
+
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: fonts.text.clone(),
+                        font_size: 30.,
+                        color: Color::WHITE,
+                    },
+                ))
+                .insert_bundle((LoadingText, ScaledFont(30.)));
         });
 
     commands.insert_resource(SplashTimer(Timer::from_seconds(1.0, false)));
 }
 
-fn timer_finished(time: Res<Time>, timer: Option<ResMut<SplashTimer>>) -> bool {
-    timer.map_or(false, |mut timer| {
-        timer.tick(time.delta());
+/// Waits for every asset to be loaded, and for the splash to be shown long enough, before opening the main menu.
+///
+/// Missing optional assets are logged and ignored, whereas missing required ones are listed on the splash screen
+/// which is never left.
+fn track_loading(
+    mut commands: Commands,
+    mut timer: ResMut<SplashTimer>,
+    mut is_reported: Local<bool>,
+    loading: Loading,
+    time: Res<Time>,
+    mut progress_bar_query: Query<&mut Style, With<ProgressBar>>,
+    mut text_query: Query<&mut Text, With<LoadingText>>,
+) {
+    let progress = loading.progress();
+
+    progress_bar_query.for_each_mut(|mut style| style.size.width = Val::Percent(progress.ratio() * 100.0));
 
-        timer.finished()
-    })
+    if !progress.is_settled() {
+        text_query.for_each_mut(|mut text| text.sections[0].value = format!("{:.0}%", progress.ratio() * 100.0));
+
+        return;
+    }
+
+    if !*is_reported {
+        report_missing_assets(&progress);
+        *is_reported = true;
+    }
+
+    if !progress.missing_required.is_empty() {
+        let report = format!("Missing assets: {}", progress.missing_required.join(", "));
+
+        text_query.for_each_mut(|mut text| text.sections[0].value.clone_from(&report));
+
+        return;
+    }
+
+    if timer.tick(time.delta()).finished() {
+        commands.insert_resource(NextState(AppScreen::MainMenu));
+    }
 }
 
-fn change_state(mut commands: Commands) {
-    commands.insert_resource(NextState(AppScreen::MainMenu));
+fn report_missing_assets(progress: &LoadProgress) {
+    for path in &progress.missing_required {
+        error!("cannot load required asset {path}");
+    }
+
+    for path in &progress.missing_optional {
+        warn!("cannot load {path}, playing without it");
+    }
 }
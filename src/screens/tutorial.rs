@@ -0,0 +1,246 @@
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::assets::FontAssets;
+use crate::audio::SoundEffect;
+use crate::display::ScaledFont;
+use crate::game::apple::{Apple, AppleSpawner};
+use crate::game::bush::Bush;
+use crate::game::components::{Direction, Lifetime, Position};
+use crate::game::level::Level;
+use crate::game::snake::{Dead, Snake, SnakeEvent};
+use crate::i18n::Localized;
+use crate::settings::Settings;
+use crate::states::{AppScreen, GameStatus};
+use crate::systems;
+
+const TUTORIAL_LEVEL: &str = "maps/tutorial.json";
+/// Lifetime of the apples once the player is taught about it, longer than in a regular game.
+const APPLE_LIFETIME: f32 = 8.0;
+
+/// Action the player is asked to perform to move on with the tutorial.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TutorialStep {
+    #[default]
+    Turn,
+    Eat,
+    WrapAround,
+    Obstacle,
+    AppleLifetime,
+    Done,
+}
+
+impl TutorialStep {
+    pub const fn prompt(self) -> &'static str {
+        match self {
+            Self::Turn => "Press a direction key to turn",
+            Self::Eat => "Eat an apple to grow",
+            Self::WrapAround => "Cross an edge of the map to come out on the other side",
+            Self::Obstacle => "Bushes stop the snake, slip past one without touching it",
+            Self::AppleLifetime => "Apples rot after a while, eat one before it disappears",
+            Self::Done => "Well done! Press Enter to start playing",
+        }
+    }
+
+    const fn next(self) -> Self {
+        match self {
+            Self::Turn => Self::Eat,
+            Self::Eat => Self::WrapAround,
+            Self::WrapAround => Self::Obstacle,
+            Self::Obstacle => Self::AppleLifetime,
+            Self::AppleLifetime | Self::Done => Self::Done,
+        }
+    }
+}
+
+/// Tutorial being played instead of the current level, kept across retries along with the progress of the player.
+pub struct Tutorial {
+    pub level: Handle<Level>,
+    pub step: TutorialStep,
+    /// Position and direction of the head on the previous frame, to tell when the snake turns or wraps around.
+    last_head: Option<(IVec3, Direction)>,
+}
+
+impl Tutorial {
+    pub fn new(asset_server: &AssetServer) -> Self {
+        Self {
+            level: asset_server.load(TUTORIAL_LEVEL),
+            step: TutorialStep::default(),
+            last_head: None,
+        }
+    }
+}
+
+#[derive(Component)]
+struct TutorialPrompt;
+
+/// Root of the prompts, drawn over the game.
+#[derive(Component)]
+struct TutorialOverlay;
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_enter_system(GameStatus::Tutorial, setup_tutorial)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(AppScreen::InGame)
+                    .run_in_state(GameStatus::Tutorial)
+                    .run_if_resource_exists::<Tutorial>()
+                    .with_system(advance_tutorial)
+                    .with_system(update_prompt)
+                    .with_system(pace_apples.run_if_resource_exists::<AppleSpawner>())
+                    .with_system(finish_tutorial)
+                    .into(),
+            )
+            .add_exit_system(GameStatus::Tutorial, systems::despawn_with::<TutorialOverlay>)
+            // the progress is only kept while retrying
+            .add_enter_system(AppScreen::MainMenu, systems::despawn_resource::<Tutorial>);
+    }
+}
+
+fn setup_tutorial(mut commands: Commands, fonts: Res<FontAssets>, tutorial: Option<Res<Tutorial>>) {
+    let step = tutorial.map_or_else(TutorialStep::default, |tutorial| tutorial.step);
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                justify_content: JustifyContent::FlexEnd,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                padding: UiRect {
+                    bottom: Val::Px(40.0),
+                    ..default()
+                },
+                ..Default::default()
+            },
+            color: UiColor(Color::NONE),
+            ..Default::default()
+        })
+        .insert(TutorialOverlay)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    step.prompt(),
+                    TextStyle {
+                        font: fonts.text.clone(),
+                        font_size: 48.0,
+                        color: Color::WHITE,
+                    },
+                ))
+                .insert_bundle((TutorialPrompt, ScaledFont(48.0), Localized(step.prompt())));
+
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    "Press Escape to skip the tutorial",
+                    TextStyle {
+                        font: fonts.text.clone(),
+                        font_size: 24.0,
+                        color: Color::rgb(0.8, 0.8, 0.8),
+                    },
+                ))
+                .insert_bundle((ScaledFont(24.0), Localized("Press Escape to skip the tutorial")));
+        });
+}
+
+/// Moves on to the next step as soon as the player performs the action of the current one.
+fn advance_tutorial(
+    mut tutorial: ResMut<Tutorial>,
+    mut snake_reader: EventReader<SnakeEvent>,
+    mut sound_writer: EventWriter<SoundEffect>,
+    snake_query: Query<&Snake, Without<Dead>>,
+    head_query: Query<(&Position, &Direction)>,
+    bush_query: Query<&Position, With<Bush>>,
+) {
+    let has_grown = snake_reader
+        .iter()
+        .any(|event| matches!(event, SnakeEvent::Grow { .. }));
+    let head = snake_query
+        .iter()
+        .next()
+        .and_then(|snake| head_query.get(snake.fragments[0]).ok());
+
+    let (position, direction) = match head {
+        Some((position, direction)) => (position.0, *direction),
+        None => return,
+    };
+
+    let is_done = match tutorial.step {
+        TutorialStep::Turn => tutorial
+            .last_head
+            .is_some_and(|(_, last_direction)| last_direction != direction),
+        TutorialStep::Eat | TutorialStep::AppleLifetime => has_grown,
+        // the head jumps across the map instead of moving by a single tile
+        TutorialStep::WrapAround => tutorial
+            .last_head
+            .is_some_and(|(last_position, _)| (position - last_position).abs().max_element() > 1),
+        TutorialStep::Obstacle => bush_query.iter().any(|bush| {
+            let distance = (bush.0 - position).truncate().abs();
+
+            distance.x + distance.y == 1
+        }),
+        TutorialStep::Done => false,
+    };
+
+    tutorial.last_head = Some((position, direction));
+
+    if is_done {
+        tutorial.step = tutorial.step.next();
+        sound_writer.send(SoundEffect::MenuSelect);
+    }
+}
+
+fn update_prompt(tutorial: Res<Tutorial>, mut query: Query<&mut Localized, With<TutorialPrompt>>) {
+    if !tutorial.is_changed() {
+        return;
+    }
+
+    query.for_each_mut(|mut localized| {
+        if localized.0 != tutorial.step.prompt() {
+            localized.0 = tutorial.step.prompt();
+        }
+    });
+}
+
+/// Keeps the apples from rotting until the player is taught about it, then lets every apple rot.
+fn pace_apples(
+    mut commands: Commands,
+    tutorial: Res<Tutorial>,
+    mut apple_spawner: ResMut<AppleSpawner>,
+    apple_query: Query<Entity, (With<Apple>, Without<Lifetime>)>,
+) {
+    if tutorial.step < TutorialStep::AppleLifetime {
+        if apple_spawner.apple_lifetime.is_some() {
+            apple_spawner.apple_lifetime = None;
+        }
+
+        return;
+    }
+
+    if apple_spawner.apple_lifetime.is_none() {
+        apple_spawner.apple_lifetime = Some(Lifetime::from_seconds(APPLE_LIFETIME));
+
+        apple_query.for_each(|entity| {
+            commands.entity(entity).insert(Lifetime::from_seconds(APPLE_LIFETIME));
+        });
+    }
+}
+
+/// Starts a regular game once the tutorial is over or skipped, never offering it again on play.
+fn finish_tutorial(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    tutorial: Res<Tutorial>,
+    mut settings: ResMut<Settings>,
+) {
+    let is_finished = tutorial.step == TutorialStep::Done && keys.just_pressed(KeyCode::Return);
+
+    if is_finished || keys.just_pressed(KeyCode::Escape) {
+        settings.tutorial_completed = true;
+
+        commands.remove_resource::<Tutorial>();
+        commands.insert_resource(NextState(AppScreen::InGame));
+    }
+}
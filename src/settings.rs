@@ -0,0 +1,318 @@
+use bevy::prelude::*;
+use bevy::window::WindowMode;
+use iyes_loopless::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game::input_controller::ControlSettings;
+use crate::game::play_mode::PlayMode;
+use crate::i18n::Language;
+use crate::skin::DEFAULT_SKIN;
+use crate::storage::{self, StorageError};
+
+const SETTINGS_FILE: &str = "settings.json";
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    Fullscreen,
+}
+
+impl From<DisplayMode> for WindowMode {
+    fn from(mode: DisplayMode) -> Self {
+        match mode {
+            DisplayMode::Windowed => Self::Windowed,
+            DisplayMode::Fullscreen => Self::BorderlessFullscreen,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowSettings {
+    pub width: f32,
+    pub height: f32,
+    pub mode: DisplayMode,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            width: 1920.,
+            height: 1080.,
+            mode: DisplayMode::Windowed,
+        }
+    }
+}
+
+impl DisplayMode {
+    pub const ALL: [Self; 2] = [Self::Windowed, Self::Fullscreen];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Windowed => "Windowed",
+            Self::Fullscreen => "Fullscreen",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VolumeCategory {
+    Music,
+    Effects,
+}
+
+/// Volumes between `0.0` and `1.0`, the music and the effects being scaled by the master one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master: f32,
+    pub music: f32,
+    pub effects: f32,
+}
+
+impl AudioSettings {
+    /// Returns the volume at which the sounds of the given category are played.
+    pub fn volume(&self, category: VolumeCategory) -> f32 {
+        let volume = match category {
+            VolumeCategory::Music => self.music,
+            VolumeCategory::Effects => self.effects,
+        };
+
+        (self.master * volume).clamp(0.0, 1.0)
+    }
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            master: 0.8,
+            music: 0.6,
+            effects: 0.8,
+        }
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SnakeSpeed {
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+}
+
+impl SnakeSpeed {
+    pub const ALL: [Self; 3] = [Self::Slow, Self::Normal, Self::Fast];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Slow => "Slow",
+            Self::Normal => "Normal",
+            Self::Fast => "Fast",
+        }
+    }
+
+    /// Delay between two moves of the snake, in seconds.
+    pub const fn move_interval(self) -> f32 {
+        match self {
+            Self::Slow => 0.18,
+            Self::Normal => 0.125,
+            Self::Fast => 0.09,
+        }
+    }
+}
+
+/// What happens to the snake when the level file is modified while playing.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SnakeReload {
+    #[default]
+    Keep,
+    Reset,
+}
+
+impl SnakeReload {
+    pub const ALL: [Self; 2] = [Self::Keep, Self::Reset];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Keep => "Keep snake",
+            Self::Reset => "Reset snake",
+        }
+    }
+}
+
+/// Colors of the game, with alternatives easier to tell apart for colorblind players.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorPalette {
+    #[default]
+    Default,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+}
+
+impl ColorPalette {
+    pub const ALL: [Self; 4] = [Self::Default, Self::Deuteranopia, Self::Protanopia, Self::Tritanopia];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Default => "Default",
+            Self::Deuteranopia => "Deuteranopia",
+            Self::Protanopia => "Protanopia",
+            Self::Tritanopia => "Tritanopia",
+        }
+    }
+}
+
+/// User preferences persisted between two runs of the game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub window: WindowSettings,
+    pub audio: AudioSettings,
+    pub snake_speed: SnakeSpeed,
+    pub level_reload: SnakeReload,
+    pub controls: ControlSettings,
+    pub palette: ColorPalette,
+    pub language: Language,
+    /// Identifier of the snake skin.
+    pub skin: String,
+    /// Whether the tutorial was played through or skipped, so that it is no longer offered on play.
+    pub tutorial_completed: bool,
+    /// Mode last chosen in the main menu.
+    pub play_mode: PlayMode,
+    /// Enables the rewind key, practice games being left out of the high scores.
+    pub practice_mode: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            window: WindowSettings::default(),
+            audio: AudioSettings::default(),
+            snake_speed: SnakeSpeed::default(),
+            level_reload: SnakeReload::default(),
+            controls: ControlSettings::default(),
+            palette: ColorPalette::default(),
+            language: Language::default(),
+            skin: DEFAULT_SKIN.to_string(),
+            tutorial_completed: false,
+            play_mode: PlayMode::default(),
+            practice_mode: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Loads the settings, falling back to the default ones if they are missing or invalid.
+    ///
+    /// The settings are loaded before the logger is set up, so the error is returned to be reported later on by
+    /// inserting it as [`InvalidSettings`].
+    pub fn load() -> (Self, Option<InvalidSettings>) {
+        match storage::load(SETTINGS_FILE) {
+            Ok(settings) => (settings.unwrap_or_default(), None),
+            Err(e) => (Self::default(), Some(InvalidSettings(e))),
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = storage::save(SETTINGS_FILE, self) {
+            error!("cannot save settings: {e}");
+        }
+    }
+}
+
+/// Reason why the settings on disk were ignored, reported once the logger is set up.
+#[derive(Debug)]
+pub struct InvalidSettings(pub StorageError);
+
+/// Value given on the command line in place of the one of the settings.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Override<T> {
+    pub value: T,
+    /// Value of the settings on disk, written back instead of the override.
+    pub persisted: T,
+}
+
+impl<T: Copy + PartialEq> Override<T> {
+    /// Puts the persisted value back, unless the setting was changed while playing.
+    fn restore(&self, setting: &mut T) {
+        if *setting == self.value {
+            *setting = self.persisted;
+        }
+    }
+}
+
+/// Settings overridden from the command line, which are not written on disk unless changed while playing.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SettingsOverrides {
+    pub snake_speed: Option<Override<SnakeSpeed>>,
+    pub window_mode: Option<Override<DisplayMode>>,
+    pub play_mode: Option<Override<PlayMode>>,
+}
+
+impl SettingsOverrides {
+    /// Returns the settings to write on disk, without the overrides.
+    pub fn persisted(&self, settings: &Settings) -> Settings {
+        let mut settings = settings.clone();
+
+        if let Some(snake_speed) = self.snake_speed {
+            snake_speed.restore(&mut settings.snake_speed);
+        }
+
+        if let Some(window_mode) = self.window_mode {
+            window_mode.restore(&mut settings.window.mode);
+        }
+
+        if let Some(play_mode) = self.play_mode {
+            play_mode.restore(&mut settings.play_mode);
+        }
+
+        settings
+    }
+}
+
+/// Delay before writing the settings on disk, so that a burst of changes is written once.
+#[derive(Deref, DerefMut)]
+struct SaveTimer(Timer);
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let mut timer = Timer::from_seconds(1.0, false);
+        timer.pause();
+
+        app.init_resource::<SettingsOverrides>()
+            .insert_resource(SaveTimer(timer))
+            .add_startup_system(report_invalid_settings.run_if_resource_exists::<InvalidSettings>())
+            .add_system(save_settings);
+    }
+}
+
+fn report_invalid_settings(mut commands: Commands, invalid_settings: Res<InvalidSettings>) {
+    warn!("ignoring settings: {}", invalid_settings.0);
+
+    commands.remove_resource::<InvalidSettings>();
+}
+
+fn save_settings(
+    settings: Res<Settings>,
+    overrides: Res<SettingsOverrides>,
+    mut timer: ResMut<SaveTimer>,
+    time: Res<Time>,
+) {
+    if settings.is_changed() && !settings.is_added() {
+        timer.reset();
+        timer.unpause();
+    }
+
+    if !timer.paused() && timer.tick(time.delta()).just_finished() {
+        overrides.persisted(&settings).save();
+        timer.pause();
+    }
+}
@@ -0,0 +1,210 @@
+use std::path::Path;
+
+use bevy::asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use serde::{Deserialize, Serialize};
+
+use crate::assets::{AssetCollection, SnakeAtlas, SnakeFragmentTextureAssets};
+use crate::settings::Settings;
+
+pub const DEFAULT_SKIN: &str = "classic";
+
+/// Folder scanned for `*.skin.json` manifests, every manifest found being a skin.
+const SKIN_FOLDER: &str = "skins";
+
+/// Textures of a fragment variant, relative to the assets folder.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FragmentManifest {
+    pub straight: String,
+    pub left: String,
+    pub right: String,
+}
+
+/// Description of a skin as written by artists, texture paths being relative to the assets folder.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkinManifest {
+    pub name: String,
+    pub head: String,
+    pub tail: String,
+    /// Variants of body fragments, picked at random as the snake grows.
+    pub fragments: Vec<FragmentManifest>,
+    #[serde(default)]
+    pub tongue: Option<String>,
+}
+
+/// Loaded skin, identified by the name of its manifest file without extension.
+#[derive(Debug, TypeUuid)]
+#[uuid = "5d3b8b0e-7c2a-4f5e-9a61-3f0c2e8d4b17"]
+pub struct Skin {
+    pub id: String,
+    pub name: String,
+    pub head: Handle<Image>,
+    pub tail: Handle<Image>,
+    pub fragments: Vec<SnakeFragmentTextureAssets>,
+    pub tongue: Option<Handle<Image>>,
+}
+
+impl Skin {
+    /// Returns the range of the fragment variants, to draw them from a number generator.
+    pub fn variant_range(&self) -> std::ops::Range<u16> {
+        0..self.fragments.len().max(1) as u16
+    }
+}
+
+#[derive(Default)]
+pub struct SkinLoader;
+
+impl AssetLoader for SkinLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let manifest = serde_json::from_slice::<SkinManifest>(bytes)?;
+
+            if manifest.fragments.is_empty() {
+                return Err(bevy::asset::Error::msg("a skin needs at least one fragment variant"));
+            }
+
+            let id = skin_id(load_context.path());
+            let mut dependencies = Vec::new();
+            let mut load_texture = |path: &str| {
+                let asset_path = AssetPath::from(path).to_owned();
+                let handle = load_context.get_handle(asset_path.clone());
+
+                dependencies.push(asset_path);
+
+                handle
+            };
+
+            let skin = Skin {
+                id,
+                name: manifest.name,
+                head: load_texture(&manifest.head),
+                tail: load_texture(&manifest.tail),
+                fragments: manifest
+                    .fragments
+                    .iter()
+                    .map(|fragment| SnakeFragmentTextureAssets {
+                        straight: load_texture(&fragment.straight),
+                        right_curved: load_texture(&fragment.right),
+                        left_curved: load_texture(&fragment.left),
+                    })
+                    .collect(),
+                tongue: manifest.tongue.as_deref().map(&mut load_texture),
+            };
+
+            load_context.set_default_asset(LoadedAsset::new(skin).with_dependencies(dependencies));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["skin.json"]
+    }
+}
+
+/// Strips every extension from the manifest file name, `classic.skin.json` being the `classic` skin.
+fn skin_id(path: &Path) -> String {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.split('.').next())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Every skin found in the skins folder.
+pub struct SkinCatalog {
+    pub skins: Vec<Handle<Skin>>,
+}
+
+impl FromWorld for SkinCatalog {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.get_resource::<AssetServer>().unwrap();
+
+        let skins = asset_server
+            .load_folder(SKIN_FOLDER)
+            .unwrap_or_else(|e| {
+                error!("cannot list skins: {e:?}");
+
+                Vec::new()
+            })
+            .into_iter()
+            .map(HandleUntyped::typed)
+            .collect();
+
+        Self { skins }
+    }
+}
+
+impl SkinCatalog {
+    /// Returns the loaded skins, sorted by name.
+    pub fn loaded<'a>(&self, skins: &'a Assets<Skin>) -> Vec<&'a Skin> {
+        let mut loaded = self
+            .skins
+            .iter()
+            .filter_map(|handle| skins.get(handle))
+            .collect::<Vec<_>>();
+        loaded.sort_by(|a, b| a.name.cmp(&b.name));
+
+        loaded
+    }
+
+    /// Returns the skin with the given identifier, falling back to the default one.
+    pub fn find<'a>(&self, id: &str, skins: &'a Assets<Skin>) -> Option<&'a Skin> {
+        let loaded = self.loaded(skins);
+
+        loaded
+            .iter()
+            .find(|skin| skin.id == id)
+            .or_else(|| loaded.iter().find(|skin| skin.id == DEFAULT_SKIN))
+            .copied()
+    }
+}
+
+/// A broken skin does not prevent from playing with another one.
+impl AssetCollection for SkinCatalog {
+    const IS_REQUIRED: bool = false;
+
+    fn handles(&self) -> Vec<HandleUntyped> {
+        self.skins.iter().map(Handle::clone_untyped).collect()
+    }
+}
+
+pub struct SkinPlugin;
+
+impl Plugin for SkinPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Skin>()
+            .init_asset_loader::<SkinLoader>()
+            .init_resource::<SkinCatalog>()
+            .add_system(build_snake_atlas);
+    }
+}
+
+/// Packs the textures of the selected skin once they are loaded, and again whenever another skin is selected.
+fn build_snake_atlas(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    catalog: Res<SkinCatalog>,
+    skins: Res<Assets<Skin>>,
+    snake_atlas: Option<Res<SnakeAtlas>>,
+    mut images: ResMut<Assets<Image>>,
+    mut atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    let Some(skin) = catalog.find(&settings.skin, &skins) else {
+        return;
+    };
+
+    if snake_atlas.is_some_and(|snake_atlas| snake_atlas.skin == skin.id) {
+        return;
+    }
+
+    if let Some(snake_atlas) = SnakeAtlas::build(skin, &mut images, &mut atlases) {
+        commands.insert_resource(snake_atlas);
+    }
+}
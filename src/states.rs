@@ -3,6 +3,10 @@ pub enum AppScreen {
     Splash,
     MainMenu,
     InGame,
+    Editor,
+    Settings,
+    Controls,
+    LevelSelect,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -10,10 +14,12 @@ pub enum GameStatus {
     Tutorial,
     Running,
     Paused,
+    GameOver,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum GameMode {
+    #[default]
     SinglePlayer,
     MultiPlayer,
 }
@@ -0,0 +1,50 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::path::PathBuf;
+
+use derive_more::Display;
+use directories::ProjectDirs;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+#[derive(Debug, Display)]
+pub enum StorageError {
+    #[display(fmt = "cannot access {}: {}", "_0.display()", _1)]
+    Io(PathBuf, io::Error),
+    #[display(fmt = "invalid content in {}: {}", "_0.display()", _1)]
+    Json(PathBuf, serde_json::Error),
+}
+
+impl std::error::Error for StorageError {}
+
+/// Returns where the given file is stored, favoring the user data directory over the working directory.
+pub fn path(file: &str) -> PathBuf {
+    ProjectDirs::from("com", "alekece", "bevy-snake2d")
+        .map_or_else(PathBuf::new, |dirs| dirs.data_dir().to_path_buf())
+        .join(file)
+}
+
+/// Loads the given file from the storage, returning `None` if it does not exist yet.
+pub fn load<T: DeserializeOwned>(file: &str) -> Result<Option<T>, StorageError> {
+    let path = path(file);
+
+    match File::open(&path) {
+        Ok(reader) => serde_json::from_reader(BufReader::new(reader))
+            .map(Some)
+            .map_err(|e| StorageError::Json(path, e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(StorageError::Io(path, e)),
+    }
+}
+
+pub fn save<T: Serialize>(file: &str, value: &T) -> Result<(), StorageError> {
+    let path = path(file);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| StorageError::Io(parent.to_path_buf(), e))?;
+    }
+
+    let writer = File::create(&path).map_err(|e| StorageError::Io(path.clone(), e))?;
+
+    serde_json::to_writer_pretty(BufWriter::new(writer), value).map_err(|e| StorageError::Json(path, e))
+}
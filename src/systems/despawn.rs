@@ -1,17 +1,19 @@
 use bevy::ecs::system::Resource;
 use bevy::prelude::*;
 
-pub fn despawn_all(mut commands: Commands, query: Query<Entity>) {
+pub fn despawn_with<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
     query.for_each(|e| commands.entity(e).despawn_recursive());
 }
 
-// pub fn despawn_with<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
-//     query.for_each(|e| commands.entity(e).despawn_recursive());
-// }
+pub fn despawn_without<T: Component>(mut commands: Commands, query: Query<Entity, Without<T>>) {
+    query.for_each(|e| commands.entity(e).despawn_recursive());
+}
 
-// pub fn despawn_without<T: Component>(mut commands: Commands, query: Query<Entity, Without<T>>) {
-//     query.for_each(|e| commands.entity(e).despawn_recursive());
-// }
+pub fn remove_component<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
+    query.for_each(|e| {
+        commands.entity(e).remove::<T>();
+    });
+}
 
 pub fn despawn_resource<T: Resource>(mut commands: Commands) {
     commands.remove_resource::<T>();